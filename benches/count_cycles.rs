@@ -0,0 +1,50 @@
+// Instructions per second with and without cycle bookkeeping.
+// Run with `cargo bench --bench count_cycles`.
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use nmos6502::asm::Assembler;
+use nmos6502::flat_bus::FlatBus;
+use nmos6502::nmos6502::Nmos6502;
+
+const INSTRUCTIONS: u32 = 20_000_000;
+
+// A copy loop mixing indexed loads and stores, ALU ops, a page-crossing read
+// and a taken branch, so every kind of cycle adjustment is exercised.
+fn program() -> Vec<u8> {
+    let body = Assembler::new()
+        .lda_abs_x(0x10F0)
+        .adc_imm(0x01)
+        .sta_abs_y(0x2000)
+        .lda_ind_y(0x10)
+        .inx()
+        .iny();
+    let offset = -(body.len() as i8) - 2;
+    Assembler::new().data(&body.assemble()).bne(offset).jmp_abs(0x0400).assemble()
+}
+
+fn time(count_cycles:bool) -> Duration {
+    let mut bus = FlatBus::new();
+    bus.load(0x0400, &program());
+    bus.load(0x0010, &[0xF0, 0x30]);
+    let mut cpu = Nmos6502::with_state(0x0400, 0, 0, 0, 0xFF, 0x30);
+    cpu.count_cycles = count_cycles;
+
+    let start = Instant::now();
+    for _ in 0..INSTRUCTIONS {
+        cpu.tick(black_box(&mut bus));
+    }
+    black_box(cpu.state_hash());
+    start.elapsed()
+}
+
+fn main() {
+    // warm up caches and the branch predictor
+    time(true);
+    let counted = time(true);
+    let uncounted = time(false);
+    let per_instruction = |elapsed:Duration| elapsed.as_nanos() as f64 / INSTRUCTIONS as f64;
+    println!("count_cycles = true:  {:?} ({:.2} ns/instruction)", counted, per_instruction(counted));
+    println!("count_cycles = false: {:?} ({:.2} ns/instruction)", uncounted, per_instruction(uncounted));
+    println!("speedup: {:.2}x", counted.as_secs_f64() / uncounted.as_secs_f64());
+}
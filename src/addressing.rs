@@ -0,0 +1,21 @@
+// Pure effective address calculations. Each returns whether indexing carried
+// into the high byte; callers decide whether that costs a cycle.
+
+pub fn indexed(base:u16, index:u8) -> (u16, bool) {
+    (base.wrapping_add(index as u16), (base as u8).overflowing_add(index).1)
+}
+
+pub fn abs_indexed(lo:u8, hi:u8, index:u8) -> (u16, bool) {
+    indexed(u16::from_le_bytes([lo, hi]), index)
+}
+
+// Zero page indexing wraps within page zero, so there is never a crossing.
+pub fn zero_page_indexed(base:u8, index:u8) -> u16 {
+    base.wrapping_add(index) as u16
+}
+
+// Where NMOS JMP ($nnnn) reads the target's high byte: the pointer's low byte
+// wraps without carrying, so JMP ($10FF) reads $10FF and $1000.
+pub fn jmp_indirect_hi_addr(pointer:u16) -> u16 {
+    (pointer & 0xFF00) | (pointer.wrapping_add(1) & 0x00FF)
+}
@@ -0,0 +1,146 @@
+use alloc::vec::Vec;
+use crate::opcodes::Opcode;
+
+// Builds test programs in memory, one method per documented opcode and
+// addressing mode, eg. `Assembler::new().lda_imm(0x42).sta_abs(0x0200).brk().assemble()`.
+// No labels: branches take their offset, and JMP/JSR their address.
+//
+// Method names are the lowercase mnemonic plus the mode: `_a` (accumulator),
+// `_imm`, `_zp`, `_zp_x`, `_zp_y`, `_abs`, `_abs_x`, `_abs_y`, `_ind`,
+// `_ind_x` and `_ind_y`. Implied opcodes and branches are just the mnemonic.
+#[derive(Clone,Debug,Default)]
+pub struct Assembler {
+    bytes: Vec<u8>,
+}
+
+macro_rules! implied {
+    ($($name:ident => $op:ident),*) => {
+        $(pub fn $name(self) -> Self {
+            self.emit(&[Opcode::$op.as_byte()])
+        })*
+    };
+}
+
+macro_rules! byte_operand {
+    ($($name:ident => $op:ident),*) => {
+        $(pub fn $name(self, operand:u8) -> Self {
+            self.emit(&[Opcode::$op.as_byte(), operand])
+        })*
+    };
+}
+
+macro_rules! word_operand {
+    ($($name:ident => $op:ident),*) => {
+        $(pub fn $name(self, operand:u16) -> Self {
+            let [lo, hi] = operand.to_le_bytes();
+            self.emit(&[Opcode::$op.as_byte(), lo, hi])
+        })*
+    };
+}
+
+macro_rules! relative {
+    ($($name:ident => $op:ident),*) => {
+        $(pub fn $name(self, offset:i8) -> Self {
+            self.emit(&[Opcode::$op.as_byte(), offset as u8])
+        })*
+    };
+}
+
+impl Assembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Raw bytes, for data or opcodes without a method.
+    pub fn data(self, bytes:&[u8]) -> Self {
+        self.emit(bytes)
+    }
+
+    // Bytes emitted so far, eg. to work out a branch offset.
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    pub fn assemble(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    fn emit(mut self, bytes:&[u8]) -> Self {
+        self.bytes.extend_from_slice(bytes);
+        self
+    }
+
+    implied!(
+        asl_a => ASLacc, brk => BRK, clc => CLC, cld => CLD, cli => CLI, clv => CLV, dex => DEX,
+        dey => DEY, inx => INX, iny => INY, lsr_a => LSRacc, nop => NOP, pha => PHA, php => PHP,
+        pla => PLA, plp => PLP, rol_a => ROLacc, ror_a => RORacc, rti => RTI, rts => RTS,
+        sec => SEC, sed => SED, sei => SEI, tax => TAX, tay => TAY, tsx => TSX, txa => TXA,
+        txs => TXS, tya => TYA
+    );
+
+    byte_operand!(
+        adc_imm => ADCimm, adc_ind_x => ADCindX, adc_ind_y => ADCindY, adc_zp => ADCz,
+        adc_zp_x => ADCzX,
+        and_imm => ANDimm, and_ind_x => ANDindX, and_ind_y => ANDindY, and_zp => ANDz,
+        and_zp_x => ANDzX,
+        asl_zp => ASLz, asl_zp_x => ASLzX,
+        bit_zp => BITz,
+        cmp_imm => CMPimm, cmp_ind_x => CMPindX, cmp_ind_y => CMPindY, cmp_zp => CMPz,
+        cmp_zp_x => CMPzX,
+        cpx_imm => CPX, cpx_zp => CPXz,
+        cpy_imm => CPY, cpy_zp => CPYz,
+        dec_zp => DECz, dec_zp_x => DECzX,
+        eor_imm => EORimm, eor_ind_x => EORindX, eor_ind_y => EORindY, eor_zp => EORz,
+        eor_zp_x => EORzX,
+        inc_zp => INCz, inc_zp_x => INCzx,
+        lda_imm => LDAimm, lda_ind_x => LDAindX, lda_ind_y => LDAindY, lda_zp => LDAz,
+        lda_zp_x => LDAzX,
+        ldx_imm => LDXimm, ldx_zp => LDXz, ldx_zp_y => LDXzy,
+        ldy_imm => LDYimm, ldy_zp => LDYz, ldy_zp_x => LDYzx,
+        lsr_zp => LSRz, lsr_zp_x => LSRzX,
+        ora_imm => ORAimm, ora_ind_x => ORAindX, ora_ind_y => ORAindY, ora_zp => ORAz,
+        ora_zp_x => ORAzX,
+        rol_zp => ROLz, rol_zp_x => ROLzX,
+        ror_zp => RORz, ror_zp_x => RORzX,
+        sbc_imm => SBCimm, sbc_ind_x => SBCindX, sbc_ind_y => SBCindY, sbc_zp => SBCz,
+        sbc_zp_x => SBCzX,
+        sta_ind_x => STAindX, sta_ind_y => STAindY, sta_zp => STAz, sta_zp_x => STAzX,
+        stx_zp => STXz, stx_zp_y => STXzY,
+        sty_zp => STYz, sty_zp_x => STYzX
+    );
+
+    word_operand!(
+        adc_abs => ADCabs, adc_abs_x => ADCabsX, adc_abs_y => ADCabsY,
+        and_abs => ANDabs, and_abs_x => ANDabsX, and_abs_y => ANDabsY,
+        asl_abs => ASLabs, asl_abs_x => ASLabsX,
+        bit_abs => BITabs,
+        cmp_abs => CMPabs, cmp_abs_x => CMPabsx, cmp_abs_y => CMPabsy,
+        cpx_abs => CPXabs,
+        cpy_abs => CPYabs,
+        dec_abs => DECabs, dec_abs_x => DECabsX,
+        eor_abs => EORabs, eor_abs_x => EORabsX, eor_abs_y => EORabsY,
+        inc_abs => INCabs, inc_abs_x => INCabsx,
+        jmp_abs => JMP, jmp_ind => JMPi,
+        jsr_abs => JSR,
+        lda_abs => LDAabs, lda_abs_x => LDAabsX, lda_abs_y => LDAabsY,
+        ldx_abs => LDXabs, ldx_abs_y => LDXabsY,
+        ldy_abs => LDYabs, ldy_abs_x => LDYabsX,
+        lsr_abs => LSRabs, lsr_abs_x => LSRabsX,
+        ora_abs => ORAabs, ora_abs_x => ORAabsX, ora_abs_y => ORAabsY,
+        rol_abs => ROLabs, rol_abs_x => ROLabsX,
+        ror_abs => RORabs, ror_abs_x => RORabsX,
+        sbc_abs => SBCabs, sbc_abs_x => SBCabsX, sbc_abs_y => SBCabsY,
+        sta_abs => STA, sta_abs_x => STAabsX, sta_abs_y => STAay,
+        stx_abs => STX,
+        sty_abs => STY
+    );
+
+    relative!(
+        bcc => BCC, bcs => BCS, beq => BEQ, bmi => BMI, bne => BNE, bpl => BPL, bvc => BVC,
+        bvs => BVS
+    );
+}
@@ -1,3 +1,6 @@
+use crate::opcodes::Opcode;
+use crate::variant::Variant;
+
 pub trait BusInterface {
     fn get_byte_at(&mut self, addr:u16) -> u8;
     fn set_byte_at(&mut self, addr:u16, byte: u8);
@@ -6,12 +9,46 @@ pub trait BusInterface {
     // fn zero_page_addr(index: u8, off:u8) -> u16;
     // fn abs_addr(lo:u8, hi:u8, off:u8) -> u16;
 
+    // Applied to every address before the CPU touches the bus. Override to
+    // model boards that don't decode all 16 address lines, eg. `addr & 0x1FFF`.
+    fn mask_addr(&self, addr:u16) -> u16 {
+        addr
+    }
+
+    // Little-endian word read, used for the vectors and the 65C02's indirect
+    // JMP pointers. Overrides should apply mask_addr to each byte's address, as
+    // the default does; the CPU still reports both bytes to on_bus_access.
+    fn get_word_at(&mut self, addr:u16) -> u16 {
+        let lo = self.get_byte_at(self.mask_addr(addr));
+        let hi = self.get_byte_at(self.mask_addr(addr.wrapping_add(1)));
+        u16::from_le_bytes([lo, hi])
+    }
+
+    // Called for every byte the CPU reads or writes, including opcode and
+    // operand fetches, in bus order. Useful for clocking peripherals.
+    fn on_bus_access(&mut self, _addr:u16, _value:u8, _is_write:bool) {}
+
+    // Called for every stack push and pull (JSR/RTS, interrupts, PHA/PLA...),
+    // after the matching on_bus_access. `sp` is the stack slot written or
+    // read, ie. the address within the stack page.
+    fn on_stack_op(&mut self, _sp:u8, _value:u8, _is_push:bool) {}
+
     // specifically used for opcode + param retrieval.
-    // This is the naive implementation; you may wish to override.
-    fn get_pipelined_bytes(&mut self, addr:u16) -> (u8, u8, u8) {
-        let opcode = self.get_byte_at(addr);
-        let b1 = self.get_byte_at(addr.wrapping_add(1));
-        let b2 = self.get_byte_at(addr.wrapping_add(2));
+    // Only the operand bytes the opcode uses on `variant` are read; unused
+    // slots repeat the last value on the bus (open bus). Overrides that fetch
+    // all three bytes up front must make those speculative reads side-effect
+    // free, and should apply mask_addr to each address themselves.
+    fn get_pipelined_bytes(&mut self, addr:u16, variant:Variant) -> (u8, u8, u8) {
+        let opcode = self.get_byte_at(self.mask_addr(addr));
+        let len = Opcode::decode(opcode, variant).pc_inc();
+        let b1 = if len > 1 { self.get_byte_at(self.mask_addr(addr.wrapping_add(1))) } else { opcode };
+        let b2 = if len > 2 { self.get_byte_at(self.mask_addr(addr.wrapping_add(2))) } else { b1 };
         (opcode, b1, b2)
     }
-}
\ No newline at end of file
+}
+
+// Side-effect free reads for debuggers and monitors. Implement this alongside
+// BusInterface when inspecting memory must not trigger I/O.
+pub trait BusPeek {
+    fn peek_byte_at(&self, addr:u16) -> u8;
+}
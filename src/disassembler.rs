@@ -0,0 +1,59 @@
+use core::fmt;
+use crate::opcodes::{AddressingMode, Opcode};
+
+// A decoded instruction. Formats as standard 6502 assembly, eg. `LDA $1234,X`.
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub struct Disassembly {
+    pub address: u16,
+    pub opcode: Opcode,
+    pub operands: (u8, u8),
+}
+
+impl Disassembly {
+    pub fn new(address:u16, opcode:Opcode, operands:(u8, u8)) -> Self {
+        Disassembly { address, opcode, operands }
+    }
+
+    // Bytes occupied by the instruction, opcode included.
+    pub fn length(&self) -> u16 {
+        self.opcode.pc_inc()
+    }
+
+    fn word_operand(&self) -> u16 {
+        u16::from_le_bytes([self.operands.0, self.operands.1])
+    }
+
+    // Branch offsets count from the end of the instruction.
+    fn branch_target(&self, offset:u8) -> u16 {
+        self.address
+            .wrapping_add(self.length())
+            .wrapping_add_signed(offset as i8 as i16)
+    }
+}
+
+impl fmt::Display for Disassembly {
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+        let mnemonic = self.opcode.mnemonic();
+        let zp = self.operands.0;
+        match self.opcode.addressing_mode() {
+            AddressingMode::Implied => write!(f, "{}", mnemonic),
+            AddressingMode::Accumulator => write!(f, "{} A", mnemonic),
+            AddressingMode::Immediate => write!(f, "{} #${:02X}", mnemonic, zp),
+            AddressingMode::ZeroPage => write!(f, "{} ${:02X}", mnemonic, zp),
+            AddressingMode::ZeroPageX => write!(f, "{} ${:02X},X", mnemonic, zp),
+            AddressingMode::ZeroPageY => write!(f, "{} ${:02X},Y", mnemonic, zp),
+            AddressingMode::Absolute => write!(f, "{} ${:04X}", mnemonic, self.word_operand()),
+            AddressingMode::AbsoluteX => write!(f, "{} ${:04X},X", mnemonic, self.word_operand()),
+            AddressingMode::AbsoluteY => write!(f, "{} ${:04X},Y", mnemonic, self.word_operand()),
+            AddressingMode::Indirect => write!(f, "{} (${:04X})", mnemonic, self.word_operand()),
+            AddressingMode::IndirectX => write!(f, "{} (${:02X},X)", mnemonic, zp),
+            AddressingMode::IndirectY => write!(f, "{} (${:02X}),Y", mnemonic, zp),
+            AddressingMode::ZeroPageIndirect => write!(f, "{} (${:02X})", mnemonic, zp),
+            AddressingMode::AbsoluteIndirectX => write!(f, "{} (${:04X},X)", mnemonic, self.word_operand()),
+            AddressingMode::Relative => write!(f, "{} ${:04X}", mnemonic, self.branch_target(zp)),
+            AddressingMode::ZeroPageRelative => {
+                write!(f, "{} ${:02X},${:04X}", mnemonic, zp, self.branch_target(self.operands.1))
+            },
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use core::ops::{Index, IndexMut};
+use alloc::{boxed::Box, vec};
+use crate::bus_interface::{BusInterface, BusPeek};
+
+// Plain 64K of RAM with no I/O, for quick starts and test fixtures.
+pub struct FlatBus {
+    mem: Box<[u8]>,
+}
+
+impl Default for FlatBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FlatBus {
+    pub fn new() -> Self {
+        FlatBus { mem: vec![0; 0x10000].into_boxed_slice() }
+    }
+
+    // Copies `data` in starting at `addr`, wrapping past $FFFF to $0000.
+    pub fn load(&mut self, addr:u16, data:&[u8]) {
+        for (i, byte) in data.iter().enumerate() {
+            self.mem[addr.wrapping_add(i as u16) as usize] = *byte;
+        }
+    }
+}
+
+impl BusInterface for FlatBus {
+    fn get_byte_at(&mut self, addr:u16) -> u8 {
+        self.mem[addr as usize]
+    }
+
+    fn set_byte_at(&mut self, addr:u16, byte:u8) {
+        self.mem[addr as usize] = byte;
+    }
+}
+
+impl BusPeek for FlatBus {
+    fn peek_byte_at(&self, addr:u16) -> u8 {
+        self.mem[addr as usize]
+    }
+}
+
+impl Index<u16> for FlatBus {
+    type Output = u8;
+
+    fn index(&self, addr:u16) -> &u8 {
+        &self.mem[addr as usize]
+    }
+}
+
+impl IndexMut<u16> for FlatBus {
+    fn index_mut(&mut self, addr:u16) -> &mut u8 {
+        &mut self.mem[addr as usize]
+    }
+}
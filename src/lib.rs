@@ -1,5 +1,20 @@
 #![no_std]
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
 pub mod nmos6502;
 pub mod bus_interface;
 pub mod opcodes;
-mod processor_status;
+pub mod processor_status;
+pub mod trace;
+pub mod disassembler;
+pub mod addressing;
+pub mod variant;
+#[cfg(feature = "alloc")]
+pub mod flat_bus;
+#[cfg(feature = "asm")]
+pub mod asm;
+#[cfg(feature = "wasm")]
+pub mod wasm;
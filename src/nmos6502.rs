@@ -1,5 +1,15 @@
-use crate::{opcodes::Opcode, processor_status::ProcessorStatus};
-use crate::bus_interface::BusInterface;
+use core::marker::PhantomData;
+use crate::{addressing::{abs_indexed, indexed, jmp_indirect_hi_addr, zero_page_indexed}, opcodes::{AddressingMode, Opcode}, processor_status::{ProcessorStatus, StatusFlags, STATUS_UNUSED_BITS}};
+use crate::bus_interface::{BusInterface, BusPeek};
+use crate::trace::InstructionRecord;
+use crate::variant::Variant;
+#[cfg(feature = "alloc")]
+use crate::trace::GoldenLine;
+use crate::disassembler::Disassembly;
+#[cfg(feature = "alloc")]
+use alloc::{format, string::String, vec::Vec};
+#[cfg(feature = "alloc")]
+use core::ops::Range;
 
 pub struct Nmos6502 {
     
@@ -8,20 +18,197 @@ pub struct Nmos6502 {
     processor_status: ProcessorStatus,
 
     pub last_pc_cycles:u8,
+    // Whether the last instruction paid a page-cross penalty.
+    last_page_crossed: bool,
+    // Cycles of last_pc_cycles beyond the opcode's base cost, see last_cycle_breakdown.
+    last_branch_penalty: u8,
+    last_page_cross_penalty: u8,
+    // When false, skip all cycle bookkeeping; last_pc_cycles stays 0.
+    pub count_cycles: bool,
     pub irq: bool,
     pub nmi: bool,
+    // CLI/SEI/PLP change I after the interrupt lines are polled, so the poll
+    // following one of them still sees the old I, held here.
+    polled_interrupt_disable: Option<bool>,
+    // A taken branch that stays on its page skips its last poll, so the next
+    // poll only sees the IRQ/NMI lines as they were when the branch polled.
+    branch_polled_lines: Option<(bool, bool)>,
     pub halted: bool,
+    halt_reason: Option<HaltReason>,
+    // Set by request_reset, acted on at the start of the next tick.
+    reset_pending: bool,
+    // The last interrupt sequence run, by the vector it took; see take_interrupt_ack.
+    last_interrupt: Option<InterruptKind>,
+    // total_cycles at which to assert the line, see schedule_irq/schedule_nmi.
+    irq_at: Option<u64>,
+    nmi_at: Option<u64>,
 
     pub break_flag_ext_debug: bool,
     pub uncaught_opcode_debug: Option<u8>,
+    // Halt on an unrecognized opcode instead of stepping over it.
+    pub strict_illegal: bool,
+    // Which instruction set the opcode bytes decode to.
+    pub variant: Variant,
+    // 6510 I/O port registers, mapped at $0000 (direction, 1 = output) and
+    // $0001 (data) when variant is Mos6510.
+    pub port_ddr: u8,
+    pub port_data: u8,
     pub last_pc_debug: u16,
     pub num_instructions_executed_debug:u32,
+    // Monotonic totals; reset() leaves them alone, see reset_counters.
+    total_cycles: u64,
+    total_instructions: u64,
+    last_opcode_byte: u8,
+    last_operands: (u8, u8),
+
+    // Non-authentic: when set, push/pull carry into the stack page instead of
+    // wrapping within page 1, so runaway recursion shows up as a descending
+    // 16-bit stack address. For debugging stack corruption only.
+    pub extended_stack: bool,
+    extended_stack_page: u8,
+    // When set, a push at SP=$00 or pull at SP=$FF is recorded in stack_fault.
+    // The stack still wraps as on hardware.
+    pub stack_guard: bool,
+    stack_fault: Option<StackFault>,
+
+    pub profiling: bool,
+    addressing_mode_cycles: [u64; AddressingMode::COUNT],
+    opcode_histogram: [u64; 256],
+
+    // Address of the last breakpoint hit: the PC for Exec, the accessed
+    // address for Read/Write. Cleared by the caller.
+    #[cfg(feature = "alloc")]
+    pub hit_breakpoint: Option<u16>,
+    #[cfg(feature = "alloc")]
+    breakpoints: Vec<(u16, BreakpointKind)>,
+    #[cfg(feature = "alloc")]
+    breakpoint_resume_pc: Option<u16>,
+    // Writes into a watched range: (address, value, PC of the instruction).
+    #[cfg(feature = "alloc")]
+    write_watches: Vec<Range<u16>>,
+    #[cfg(feature = "alloc")]
+    write_hits: Vec<(u16, u8, u16)>,
+
+    // First mismatch against the attached golden log: (line index, description).
+    #[cfg(feature = "alloc")]
+    pub divergence: Option<(usize, String)>,
+    #[cfg(feature = "alloc")]
+    golden_log: Vec<GoldenLine>,
+    #[cfg(feature = "alloc")]
+    golden_index: usize,
+    #[cfg(feature = "alloc")]
+    golden_cycle_base: u64,
 }
 
-enum InterruptType {
-    BRK,
-    IRQ,
-    NMI
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum CpuError {
+    // (opcode byte, PC it was fetched from)
+    UnknownOpcode(u8, u16)
+}
+
+impl core::fmt::Display for CpuError {
+    fn fmt(&self, f:&mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            CpuError::UnknownOpcode(opcode, pc) => write!(f, "unknown opcode ${:02X} at ${:04X}", opcode, pc),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CpuError {}
+
+// Layout version written by to_bytes; from_bytes rejects any other.
+pub const STATE_VERSION: u8 = 1;
+// Length of the to_bytes image: version, PC (LE), A, X, Y, SP, status, then
+// a flags byte (bit 0 irq, bit 1 nmi, bit 2 halted).
+pub const STATE_LEN: usize = 9;
+
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum ParseError {
+    // Fewer than STATE_LEN bytes; holds the length given.
+    Truncated(usize),
+    UnsupportedVersion(u8),
+}
+
+impl core::fmt::Display for ParseError {
+    fn fmt(&self, f:&mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ParseError::Truncated(len) => write!(f, "state is {} bytes, expected {}", len, STATE_LEN),
+            ParseError::UnsupportedVersion(version) => write!(f, "unsupported state version {}", version),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum HaltReason {
+    // Locked up as if by a KIL/JAM opcode; on hardware only a reset recovers.
+    Jam,
+    // 65C02 STP: stopped until reset.
+    Stp,
+    // Paused by the host.
+    UserRequest,
+    // Paused by a debugger on hitting a breakpoint.
+    Breakpoint,
+    // Unrecognized opcode with `strict_illegal` set: (opcode byte, PC)
+    UnknownOpcode(u8, u16)
+}
+
+// (PC of the instruction that wrapped the stack)
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum StackFault {
+    Overflow(u16),
+    Underflow(u16)
+}
+
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum BreakpointKind {
+    Exec,
+    Read,
+    Write
+}
+
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum RunOutcome {
+    Stopped,
+    CycleLimit,
+    Halted
+}
+
+// How last_pc_cycles splits up; all zero with count_cycles off.
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub struct CycleBreakdown {
+    // The opcode's fixed cost, including variant extras such as the 65C02's
+    // JMP ($nnnn) cycle.
+    pub base: u8,
+    // 1 when a branch was taken.
+    pub branch_penalty: u8,
+    // 1 when indexing or a taken branch crossed a page.
+    pub page_cross_penalty: u8,
+}
+
+// The instruction a step executed.
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub struct StepResult {
+    pub pc: u16,
+    pub opcode: u8,
+    pub cycles: u8,
+    pub disassembly: Disassembly,
+}
+
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum InterruptKind {
+    Brk,
+    Irq,
+    Nmi
+}
+
+impl Default for Nmos6502 {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Nmos6502 {
@@ -35,1004 +222,875 @@ impl Nmos6502 {
                 x: 0, y: 0,
                 stack_pointer: 0xFF
             },
-            processor_status: 0b0011_0000.into(),
+            // bit 5 is unused and bit 4 is B; neither exists in the register,
+            // so both always read 1 (see STATUS_UNUSED_BITS). All real flags
+            // start clear.
+            processor_status: STATUS_UNUSED_BITS.into(),
             irq: false,
             nmi: false,
+            polled_interrupt_disable: None,
+            branch_polled_lines: None,
             halted: false,
+            halt_reason: None,
+            reset_pending: false,
+            last_interrupt: None,
+            irq_at: None,
+            nmi_at: None,
             break_flag_ext_debug: true,
             uncaught_opcode_debug: None,
+            strict_illegal: false,
+            variant: Variant::Nmos6502,
+            port_ddr: 0,
+            port_data: 0,
             last_pc_debug: 0,
             num_instructions_executed_debug: 0,
-            last_pc_cycles: 0
+            total_cycles: 0,
+            total_instructions: 0,
+            last_opcode_byte: 0,
+            last_operands: (0, 0),
+            last_pc_cycles: 0,
+            last_page_crossed: false,
+            last_branch_penalty: 0,
+            last_page_cross_penalty: 0,
+            count_cycles: true,
+            extended_stack: false,
+            extended_stack_page: 0x01,
+            stack_guard: false,
+            stack_fault: None,
+            profiling: false,
+            addressing_mode_cycles: [0; AddressingMode::COUNT],
+            opcode_histogram: [0; 256],
+            #[cfg(feature = "alloc")]
+            hit_breakpoint: None,
+            #[cfg(feature = "alloc")]
+            breakpoints: Vec::new(),
+            #[cfg(feature = "alloc")]
+            breakpoint_resume_pc: None,
+            #[cfg(feature = "alloc")]
+            write_watches: Vec::new(),
+            #[cfg(feature = "alloc")]
+            write_hits: Vec::new(),
+            #[cfg(feature = "alloc")]
+            divergence: None,
+            #[cfg(feature = "alloc")]
+            golden_log: Vec::new(),
+            #[cfg(feature = "alloc")]
+            golden_index: 0,
+            #[cfg(feature = "alloc")]
+            golden_cycle_base: 0
+        }
+    }
+
+    // As new(), but starting from a known register state.
+    pub fn with_state(pc:u16, a:u8, x:u8, y:u8, sp:u8, status:u8) -> Self {
+        let mut cpu = Self::new();
+        cpu.registers = Registers {
+            program_counter: pc,
+            accumulator: a,
+            x, y,
+            stack_pointer: sp
+        };
+        cpu.processor_status = (status | STATUS_UNUSED_BITS).into();
+        cpu
+    }
+
+    // Warm reset, as pulling the RESET line: PC from the reset vector, SP
+    // lowered by 3, I set and the 6510 port made all inputs. The other
+    // registers keep their values; use power_on for the cold-start ones.
+    pub fn reset<T:BusInterface + ?Sized>(&mut self, bus:&mut T) {
+        self.reset_registers();
+        self.registers.program_counter = self.read_vector(bus, 0xFFFC);
+    }
+
+    // Cold start, as opposed to reset's warm one: documented power-on register
+    // values, then the reset vector. Counters restart at the 7 cycles the
+    // reset sequence takes, matching logs such as nestest's.
+    pub fn power_on<T:BusInterface + ?Sized>(&mut self, bus:&mut T) {
+        self.registers = Registers {
+            program_counter: 0,
+            accumulator: 0,
+            x: 0, y: 0,
+            stack_pointer: 0xFD
+        };
+        self.processor_status = ProcessorStatus::with_flags(false, false, true, false, false, false);
+        self.extended_stack_page = 0x01;
+        self.polled_interrupt_disable = None;
+        self.branch_polled_lines = None;
+        self.port_ddr = 0;
+        self.port_data = 0;
+        self.halted = false;
+        self.halt_reason = None;
+        self.registers.program_counter = self.read_vector(bus, 0xFFFC);
+
+        self.reset_counters();
+        self.last_pc_cycles = if self.count_cycles { 7 } else { 0 };
+        self.last_branch_penalty = 0;
+        self.last_page_cross_penalty = 0;
+        self.total_cycles = self.last_pc_cycles as u64;
+    }
+
+    // Writes `image` to the bus from `load_addr` and resets, so the image's
+    // own vector at $FFFC decides where it starts. Bytes past $FFFF are
+    // dropped rather than wrapped into zero page; returns how many were
+    // written.
+    pub fn load_rom<T:BusInterface + ?Sized>(&mut self, bus:&mut T, image:&[u8], load_addr:u16) -> usize {
+        let len = image.len().min(0x10000 - load_addr as usize);
+        for (i, byte) in image[..len].iter().enumerate() {
+            bus.set_byte_at(load_addr + i as u16, *byte);
         }
+        self.reset(bus);
+        len
+    }
+
+    // Latches the reset line: the next tick runs reset instead of an
+    // instruction, so it can be called from outside the emulation loop.
+    pub fn request_reset(&mut self) {
+        self.reset_pending = true;
+    }
+
+    // As reset, but starts at `addr` instead of reading the reset vector.
+    pub fn reset_to(&mut self, addr:u16) {
+        self.reset_registers();
+        self.registers.program_counter = addr;
+    }
+
+    // Reset runs the interrupt sequence with the stack writes suppressed, so
+    // SP still drops by 3, and IRQs are masked. The 6510 port reverts to all
+    // inputs. An extended stack goes back to page 1.
+    fn reset_registers(&mut self) {
+        self.port_ddr = 0;
+        self.extended_stack_page = 0x01;
+        self.halted = false;
+        self.halt_reason = None;
+        self.registers.stack_pointer = self.registers.stack_pointer.wrapping_sub(3);
+        self.processor_status.set_interrupt_disable();
+    }
+
+    // Puts the CPU in the KIL/JAM state without executing a KIL opcode.
+    pub fn jam(&mut self) {
+        self.halt(HaltReason::Jam);
+    }
+
+    // tick does nothing while halted.
+    pub fn halt(&mut self, reason:HaltReason) {
+        self.halted = true;
+        self.halt_reason = Some(reason);
     }
 
-    pub fn reset<T:BusInterface>(&mut self, bus:&mut T) {
-        let reset_vec_lo = bus.get_byte_at(0xfffc);
-        let reset_vec_hi =  bus.get_byte_at(0xfffd);
-        self.registers.program_counter = self.abs_addr(reset_vec_lo, reset_vec_hi, 0);
+    // Clears any halt, whatever the reason; see reset for the authentic way
+    // out of a Jam or Stp.
+    pub fn resume(&mut self) {
+        self.halted = false;
+        self.halt_reason = None;
     }
 
-    fn push_stack_interrupt<T:BusInterface>(&mut self, ir_type:InterruptType, bus:&mut T) {
+    pub fn halt_reason(&self) -> Option<HaltReason> {
+        self.halt_reason
+    }
+
+    // An IRQ/NMI taken by tick: the sequence takes the whole tick and, like
+    // BRK, 7 cycles.
+    fn enter_interrupt<T:BusInterface + ?Sized>(&mut self, ir_type:InterruptKind, bus:&mut T) {
+        self.push_stack_interrupt(ir_type, bus);
+        self.last_pc_cycles = if self.count_cycles { 7 } else { 0 };
+        self.last_page_crossed = false;
+        self.last_branch_penalty = 0;
+        self.last_page_cross_penalty = 0;
+        self.total_cycles += self.last_pc_cycles as u64;
+    }
+
+    fn push_stack_interrupt<T:BusInterface + ?Sized>(&mut self, ir_type:InterruptKind, bus:&mut T) {
         let pc_bytes = self.registers.program_counter.to_le_bytes();
 
         self.push_stack(bus, pc_bytes[1]);
         self.push_stack(bus, pc_bytes[0]);
 
-        let flags_mask = match ir_type { 
-            InterruptType::BRK => 0b0011_0000,
-            _ => 0b0010_0000 // NMI, IRQ
-        };
-        let status = self.processor_status.as_byte() | flags_mask;
-
+        // B only exists on the stack: set for BRK, clear for IRQ/NMI.
+        let status = self.processor_status.as_stack_byte(ir_type == InterruptKind::Brk);
         self.push_stack(bus, status);
         self.processor_status.set_interrupt_disable();
 
-        let fetch_vec = match ir_type {
-            InterruptType::NMI => 0xFFFA,
-            InterruptType::BRK => 0xFFFE,
-            InterruptType::IRQ => 0xFFFE,
+        // NMOS quirk: an NMI arriving in the first four cycles of a BRK/IRQ
+        // sequence, before the vector fetch, hijacks it onto the NMI vector,
+        // and the NMI is consumed. A line raised between ticks is already
+        // there; one from schedule_nmi is checked against its cycle, counted
+        // from total_cycles, which still holds the sequence's first cycle.
+        let (acknowledged, fetch_vec) = match ir_type {
+            InterruptKind::Nmi => (InterruptKind::Nmi, 0xFFFA),
+            InterruptKind::Brk | InterruptKind::Irq if self.nmi => {
+                self.nmi = false;
+                (InterruptKind::Nmi, 0xFFFA)
+            },
+            InterruptKind::Brk | InterruptKind::Irq if self.nmi_at.is_some_and(|at| at < self.total_cycles + 4) => {
+                self.nmi_at = None;
+                (InterruptKind::Nmi, 0xFFFA)
+            },
+            InterruptKind::Brk => (InterruptKind::Brk, 0xFFFE),
+            InterruptKind::Irq => (InterruptKind::Irq, 0xFFFE),
         };
+        self.last_interrupt = Some(acknowledged);
 
-        let reset_vec_lo = bus.get_byte_at(fetch_vec);
-        let reset_vec_hi =  bus.get_byte_at(fetch_vec+0x1);
+        self.registers.program_counter = self.read_vector(bus, fetch_vec);
+    }
 
-        self.registers.program_counter = self.abs_addr(reset_vec_lo, reset_vec_hi, 0);
+    // Little-endian vector at $FFFA (NMI), $FFFC (reset) or $FFFE (IRQ/BRK).
+    fn read_vector<T:BusInterface + ?Sized>(&mut self, bus:&mut T, addr:u16) -> u16 {
+        self.read_word(bus, addr)
     }
 
-    pub fn tick<T:BusInterface>(&mut self, bus:&mut T) {
+    pub fn tick<T:BusInterface + ?Sized>(&mut self, bus:&mut T) {
+        // unknown opcodes are still recorded in uncaught_opcode_debug
+        let _ = self.try_tick(bus);
+    }
+
+    // Like tick, but reports an unrecognized opcode as an error.
+    pub fn try_tick<T:BusInterface + ?Sized>(&mut self, bus:&mut T) -> Result<(), CpuError> {
+        // A latched reset takes the whole tick and wins over a halt.
+        if self.reset_pending {
+            self.reset_pending = false;
+            self.reset(bus);
+            return Ok(());
+        }
+
+        if self.irq_at.is_some_and(|at| self.total_cycles >= at) {
+            self.irq_at = None;
+            self.irq = true;
+        }
+        if self.nmi_at.is_some_and(|at| self.total_cycles >= at) {
+            self.nmi_at = None;
+            self.nmi = true;
+        }
+
         if self.halted {
-            return;
+            return Ok(());
         }
 
-        if self.nmi {
-            self.push_stack_interrupt(InterruptType::NMI, bus);
-            return;
-        } else if self.irq && !self.processor_status.interrupt_disable() {
-            self.push_stack_interrupt(InterruptType::IRQ, bus);
-            return;
+        let interrupt_disable = self.polled_interrupt_disable.take()
+            .unwrap_or(self.processor_status.interrupt_disable());
+        let (irq, nmi) = match self.branch_polled_lines.take() {
+            Some((irq, nmi)) => (self.irq && irq, self.nmi && nmi),
+            None => (self.irq, self.nmi),
+        };
+        if nmi {
+            self.enter_interrupt(InterruptKind::Nmi, bus);
+            return Ok(());
+        } else if irq && !interrupt_disable {
+            self.enter_interrupt(InterruptKind::Irq, bus);
+            return Ok(());
         }
         
-        let (raw_opcode_byte, pipe_byte1, pipe_byte2) = bus.get_pipelined_bytes(self.registers.program_counter);
-        let opcode:Opcode = raw_opcode_byte.into();
-        self.current_opcode = opcode;
+        // Stop in front of an exec breakpoint; the next tick resumes past it.
+        #[cfg(feature = "alloc")]
+        {
+            let pc = self.registers.program_counter;
+            if self.breakpoint_resume_pc.take() != Some(pc) && self.has_breakpoint(pc, BreakpointKind::Exec) {
+                self.hit_breakpoint = Some(pc);
+                self.breakpoint_resume_pc = Some(pc);
+                return Ok(());
+            }
+        }
+
+        #[cfg(feature = "alloc")]
+        self.check_golden_line();
+
+        let (raw_opcode_byte, pipe_byte1, pipe_byte2) = self.fetch_instruction(bus, self.registers.program_counter);
+        let opcode = Opcode::decode(raw_opcode_byte, self.variant);
+
+        let fetched = [raw_opcode_byte, pipe_byte1, pipe_byte2];
+        for (i, byte) in fetched.iter().take(opcode.pc_inc() as usize).enumerate() {
+            let addr = bus.mask_addr(self.registers.program_counter.wrapping_add(i as u16));
+            bus.on_bus_access(addr, *byte, false);
+        }
 
-        self.num_instructions_executed_debug = self.num_instructions_executed_debug.wrapping_add(1);
-        self.last_pc_cycles = opcode.cycle_inc();
-        
         // inc PC after fetch
         self.last_pc_debug = self.registers.program_counter;
         self.registers.program_counter = self.registers.program_counter.wrapping_add(opcode.pc_inc());
-        match self.current_opcode {
-            Opcode::ANDabs => {
-                let addr = self.abs_addr(pipe_byte1, pipe_byte2, 0);
-                let val = bus.get_byte_at(addr);
-                self.registers.accumulator &= val;
-                self.processor_status.update_zero_neg_flags(self.registers.accumulator);
-            },
-            Opcode::ANDabsX => {
-                let addr = self.abs_addr(pipe_byte1, pipe_byte2, self.registers.x);
-                let val = bus.get_byte_at(addr);
-                self.registers.accumulator &= val;
-                self.processor_status.update_zero_neg_flags(self.registers.accumulator);
-            },
-            Opcode::ANDabsY => {
-                let addr = self.abs_addr(pipe_byte1, pipe_byte2, self.registers.y);
-                let val = bus.get_byte_at(addr);
-                self.registers.accumulator &= val;
-                self.processor_status.update_zero_neg_flags(self.registers.accumulator);
-            },
-            Opcode::ANDimm => {
-                self.registers.accumulator &= pipe_byte1;
-                self.processor_status.update_zero_neg_flags(self.registers.accumulator);
-            },
-            Opcode::ANDindX => {
-                let addr =  self.indirect_x_addr(bus,pipe_byte1, self.registers.x);
-                let val = bus.get_byte_at(addr);
-                self.registers.accumulator &= val;
-                self.processor_status.update_zero_neg_flags(self.registers.accumulator);
-            },
-            Opcode::ANDindY => {
-                let addr = self.indirect_y_addr(bus,pipe_byte1, self.registers.y);
-                let val = bus.get_byte_at(addr);
-                self.registers.accumulator &= val;
-                self.processor_status.update_zero_neg_flags(self.registers.accumulator);
-            },
-            Opcode::ANDz => {
-                let addr = self.zero_page_addr(pipe_byte1,0);
-                let val = bus.get_byte_at(addr);
-                self.registers.accumulator &= val;
-                self.processor_status.update_zero_neg_flags(self.registers.accumulator);
-            },
-            Opcode::ANDzX => {
-                let addr = self.zero_page_addr(pipe_byte1,self.registers.x);
-                let val = bus.get_byte_at(addr);
-                self.registers.accumulator &= val;
-                self.processor_status.update_zero_neg_flags(self.registers.accumulator);
-            },
-            Opcode::ASLabs => {
-                let addr = self.abs_addr(pipe_byte1, pipe_byte2, 0);
-                let val = bus.get_byte_at(addr);
-                bus.set_byte_at(addr, self.arithmetic_shift_left(val));
-            },
-            Opcode::ASLabsX => {
-                let addr = self.abs_addr(pipe_byte1, pipe_byte2, self.registers.x);
-                let val = bus.get_byte_at(addr);
-                bus.set_byte_at(addr, self.arithmetic_shift_left(val));
-            },
-            Opcode::ASLacc => {
-                self.registers.accumulator = self.arithmetic_shift_left(self.registers.accumulator);
-            },
-            Opcode::ASLz => {
-                let addr = self.zero_page_addr(pipe_byte1, 0);
-                let val = bus.get_byte_at(addr);
-                bus.set_byte_at(addr, self.arithmetic_shift_left(val));
-            },
-            Opcode::ASLzX => {
-                let addr = self.zero_page_addr(pipe_byte1, self.registers.x);
-                let val = bus.get_byte_at(addr);
-                bus.set_byte_at(addr, self.arithmetic_shift_left(val));
-            },
-            Opcode::ADCabs => {
-                let addr = self.abs_addr(pipe_byte1, pipe_byte2, 0);
-                let val = bus.get_byte_at(addr);
-                self.add_with_carry(val);
-            },
-            Opcode::ADCabsX => {
-                let addr = self.abs_addr(pipe_byte1, pipe_byte2, self.registers.x);
-                let val = bus.get_byte_at(addr);
-                self.add_with_carry(val);
-            },
-            Opcode::ADCabsY => {
-                let addr = self.abs_addr(pipe_byte1, pipe_byte2, self.registers.y);
-                let val = bus.get_byte_at(addr);
-                self.add_with_carry(val);
-            },
-            Opcode::ADCimm => { // immediate
-                self.add_with_carry(pipe_byte1);
-            },
-            Opcode::ADCindX => {
-                let addr = self.indirect_x_addr(bus,pipe_byte1, self.registers.x);
-                let val = bus.get_byte_at(addr);
-                self.add_with_carry(val);
-            },
-            Opcode::ADCindY => {
-                let addr = self.indirect_y_addr(bus,pipe_byte1, self.registers.y);
-                let val = bus.get_byte_at(addr);
-                self.add_with_carry(val);
-            },
-            Opcode::ADCz => {
-                let addr = self.zero_page_addr(pipe_byte1, 0);
-                let val = bus.get_byte_at(addr);
-                self.add_with_carry(val);
-            },
-            Opcode::ADCzX => {
-                let addr = self.zero_page_addr(pipe_byte1, self.registers.x);
-                let val = bus.get_byte_at(addr);
-                self.add_with_carry(val);
-            },
-            Opcode::BITabs => {
-                let addr = self.abs_addr(pipe_byte1, pipe_byte2, 0);
-                let val = bus.get_byte_at(addr);
-                self.bit_test(val);
-            },
-            Opcode::BITz => {
-                let addr = self.zero_page_addr(pipe_byte1, 0);
-                let val = bus.get_byte_at(addr);
-                self.bit_test(val);
-            },
-            Opcode::DECabs => {
-                let addr = self.abs_addr(pipe_byte1, pipe_byte2, 0);
-                let val = bus.get_byte_at(addr).wrapping_sub(1);
-                self.processor_status.update_zero_neg_flags(val);
-                bus.set_byte_at(addr, val);
-            },
-            Opcode::DECabsX => {
-                let addr = self.abs_addr(pipe_byte1, pipe_byte2, self.registers.x);
-                let val = bus.get_byte_at(addr).wrapping_sub(1);
-                self.processor_status.update_zero_neg_flags(val);
-                bus.set_byte_at(addr, val);
-            },
-            Opcode::DECz => {
-                let addr = self.zero_page_addr(pipe_byte1, 0);
-                let val = bus.get_byte_at(addr).wrapping_sub(1);
-                self.processor_status.update_zero_neg_flags(val);
-                bus.set_byte_at(addr, val);
-            },
-            Opcode::DECzX => {
-                let addr = self.zero_page_addr(pipe_byte1, self.registers.x);
-                let val = bus.get_byte_at(addr).wrapping_sub(1);
-                self.processor_status.update_zero_neg_flags(val);
-                bus.set_byte_at(addr, val);
-            },
-            Opcode::EORabs => {
-                let addr = self.abs_addr(pipe_byte1, pipe_byte2, 0);
-                let val = bus.get_byte_at(addr);
-                self.registers.accumulator ^= val;
-                self.processor_status.update_zero_neg_flags(self.registers.accumulator);
-            },
-            Opcode::EORabsX => {
-                let addr = self.abs_addr(pipe_byte1, pipe_byte2, self.registers.x);
-                let val = bus.get_byte_at(addr);
-                self.registers.accumulator ^= val;
-                self.processor_status.update_zero_neg_flags(self.registers.accumulator);
-            },
-            Opcode::EORabsY => {
-                let addr = self.abs_addr(pipe_byte1, pipe_byte2, self.registers.y);
-                let val = bus.get_byte_at(addr);
-                self.registers.accumulator ^= val;
-                self.processor_status.update_zero_neg_flags(self.registers.accumulator);
-            },
-            Opcode::EORimm => {
-                self.registers.accumulator ^= pipe_byte1;
-                self.processor_status.update_zero_neg_flags(self.registers.accumulator);
-            },
-            Opcode::EORindX => {
-                let addr = self.indirect_x_addr(bus,pipe_byte1, self.registers.x);
-                let val = bus.get_byte_at(addr);
-                self.registers.accumulator ^= val;
-                self.processor_status.update_zero_neg_flags(self.registers.accumulator);
-            },
-            Opcode::EORindY => {
-                let addr = self.indirect_y_addr(bus,pipe_byte1, self.registers.y);
-                let val = bus.get_byte_at(addr);
-                self.registers.accumulator ^= val;
-                self.processor_status.update_zero_neg_flags(self.registers.accumulator);
-            },
-            Opcode::EORz => {
-                let addr = self.zero_page_addr(pipe_byte1, 0);
-                let val = bus.get_byte_at(addr);
-                self.registers.accumulator ^= val;
-                self.processor_status.update_zero_neg_flags(self.registers.accumulator);
-            },
-            Opcode::EORzX => {
-                let addr = self.zero_page_addr(pipe_byte1, self.registers.x);
-                let val = bus.get_byte_at(addr);
-                self.registers.accumulator ^= val;
-                self.processor_status.update_zero_neg_flags(self.registers.accumulator);
-            },
-            Opcode::JMP => {
-                self.registers.program_counter = self.abs_addr(pipe_byte1,pipe_byte2, 0);
-            },
-            Opcode::JMPi => {
-                let indirect_jmp_addr =self.abs_addr(pipe_byte1, pipe_byte2, 0);
-                let lo = bus.get_byte_at(indirect_jmp_addr);
-                let hi = bus.get_byte_at(indirect_jmp_addr.wrapping_add(1));
-                self.registers.program_counter = self.abs_addr(lo,hi, 0);
-            },
-            Opcode::JSR => {
-                let jmp_addr = self.abs_addr(pipe_byte1, pipe_byte2, 0);
-                let pc_rtn_addr_bytes = self.registers.program_counter.wrapping_sub(1).to_le_bytes();
-                self.push_stack(bus, pc_rtn_addr_bytes[1]);
-                self.push_stack(bus, pc_rtn_addr_bytes[0]);
-                self.registers.program_counter = jmp_addr;
-            },
-            Opcode::LDAimm => { // immediate
-                self.registers.accumulator = pipe_byte1;
-                self.processor_status.update_zero_neg_flags(self.registers.accumulator);
-            },
-            Opcode::LDAz => { // zero page
-                let get_addr = self.zero_page_addr(pipe_byte1,0);
-                self.registers.accumulator = bus.get_byte_at(get_addr);
-                self.processor_status.update_zero_neg_flags(self.registers.accumulator);
-            },
-            Opcode::LDAzX => { // zero page
-                let get_addr = self.zero_page_addr(pipe_byte1,self.registers.x);
-                self.registers.accumulator = bus.get_byte_at(get_addr);
-                self.processor_status.update_zero_neg_flags(self.registers.accumulator);
-            },
-            Opcode::LDAabs => { // absolute
-                let get_addr = self.abs_addr(pipe_byte1, pipe_byte2, 0);
-                self.registers.accumulator = bus.get_byte_at(get_addr);
-                self.processor_status.update_zero_neg_flags(self.registers.accumulator);
-            },
-            Opcode::LDAabsX => {
-                let get_addr = self.abs_addr(pipe_byte1, pipe_byte2, self.registers.x);
-                self.registers.accumulator = bus.get_byte_at(get_addr);
-                self.processor_status.update_zero_neg_flags(self.registers.accumulator);
-            },
-            Opcode::LDAabsY => {
-                let get_addr = self.abs_addr(pipe_byte1, pipe_byte2, self.registers.y);
-                self.registers.accumulator = bus.get_byte_at(get_addr);
-                self.processor_status.update_zero_neg_flags(self.registers.accumulator);
-            },
-            Opcode::LDAindX => {
-                let addr = self.indirect_x_addr(bus,pipe_byte1, self.registers.x);
+        self.execute(bus, raw_opcode_byte, pipe_byte1, pipe_byte2)
+    }
 
-                self.registers.accumulator = bus.get_byte_at(addr);
-                self.processor_status.update_zero_neg_flags(self.registers.accumulator);
-            },
-            Opcode::LDAindY => {
-                let addr = self.indirect_y_addr(bus,pipe_byte1, self.registers.y);
-                self.registers.accumulator = bus.get_byte_at(addr);
-                self.processor_status.update_zero_neg_flags(self.registers.accumulator);
-            },
-            Opcode::LDXabs => {
-                let get_addr = self.abs_addr(pipe_byte1, pipe_byte2, 0);
-                self.registers.x = bus.get_byte_at(get_addr);
-                self.processor_status.update_zero_neg_flags(self.registers.x);
-            },
-            Opcode::LDXabsY => {
-                let get_addr = self.abs_addr(pipe_byte1,pipe_byte2, self.registers.y);
-                self.registers.x = bus.get_byte_at(get_addr);
-                self.processor_status.update_zero_neg_flags(self.registers.x);
-            },
-            Opcode::LDXimm => {
-                self.registers.x = pipe_byte1;
-                self.processor_status.update_zero_neg_flags(self.registers.x);
-            },
-            Opcode::LDXz => {
-                let addr = self.zero_page_addr(pipe_byte1,0);
-                self.registers.x = bus.get_byte_at(addr);
-                self.processor_status.update_zero_neg_flags(self.registers.x);
-            },
-            Opcode::LDXzy => {
-                let addr = self.zero_page_addr(pipe_byte1,self.registers.y);
-                self.registers.x = bus.get_byte_at(addr);
-                self.processor_status.update_zero_neg_flags(self.registers.x);
-            },
-            Opcode::LDYabs => {
-                let get_addr = self.abs_addr(pipe_byte1,pipe_byte2,0);
-                self.registers.y = bus.get_byte_at(get_addr);
-                self.processor_status.update_zero_neg_flags(self.registers.y);
-            },
-            Opcode::LDYabsX => {
-                let get_addr = self.abs_addr(pipe_byte1, pipe_byte2, self.registers.x);
-                self.registers.y = bus.get_byte_at(get_addr);
-                self.processor_status.update_zero_neg_flags(self.registers.y);
-            },
-            Opcode::LDYimm => {
-                self.registers.y = pipe_byte1;
-                self.processor_status.update_zero_neg_flags(self.registers.y);
-            },
-            Opcode::LDYz => {
-                let addr = self.zero_page_addr(pipe_byte1,0);
-                self.registers.y = bus.get_byte_at(addr);
-                self.processor_status.update_zero_neg_flags(self.registers.y);
-            },
-            Opcode::LDYzx => {
-                let addr = self.zero_page_addr(pipe_byte1,self.registers.x);
-                self.registers.y = bus.get_byte_at(addr);
-                self.processor_status.update_zero_neg_flags(self.registers.y);
-            },
-            Opcode::LSRabs => {
-                let addr = self.abs_addr(pipe_byte1, pipe_byte2, 0);
-                let val = bus.get_byte_at(addr);
-                bus.set_byte_at(addr, self.logical_shift_right(val));
-            },
-            Opcode::LSRabsX => {
-                let addr = self.abs_addr(pipe_byte1, pipe_byte2, self.registers.x);
-                let val = bus.get_byte_at(addr);
-                bus.set_byte_at(addr, self.logical_shift_right(val));
-            },
-            Opcode::LSRacc => {
-                self.registers.accumulator = self.logical_shift_right(self.registers.accumulator);
-            },
-            Opcode::LSRz => {
-                let addr = self.zero_page_addr(pipe_byte1, 0);
-                let val = bus.get_byte_at(addr);
-                bus.set_byte_at(addr, self.logical_shift_right(val));
-            },
-            Opcode::LSRzX => {
-                let addr = self.zero_page_addr(pipe_byte1, self.registers.x);
-                let val = bus.get_byte_at(addr);
-                bus.set_byte_at(addr, self.logical_shift_right(val));
-            },
-            Opcode::ORAabs => {
-                let addr = self.abs_addr(pipe_byte1, pipe_byte2, 0);
-                let val = bus.get_byte_at(addr);
-                self.registers.accumulator |= val;
-                self.processor_status.update_zero_neg_flags(self.registers.accumulator);
-            },
-            Opcode::ORAabsX => {
-                let addr = self.abs_addr(pipe_byte1, pipe_byte2, self.registers.x);
-                let val = bus.get_byte_at(addr);
-                self.registers.accumulator |= val;
-                self.processor_status.update_zero_neg_flags(self.registers.accumulator);
-            },
-            Opcode::ORAabsY => {
-                let addr = self.abs_addr(pipe_byte1, pipe_byte2, self.registers.y);
-                let val = bus.get_byte_at(addr);
-                self.registers.accumulator |= val;
-                self.processor_status.update_zero_neg_flags(self.registers.accumulator);
-            },
-            Opcode::ORAimm => {
-                self.registers.accumulator |= pipe_byte1;
-                self.processor_status.update_zero_neg_flags(self.registers.accumulator);
-            },
-            Opcode::ORAindX => {
-                let addr = self.indirect_x_addr(bus,pipe_byte1, self.registers.x);
-                let val = bus.get_byte_at(addr);
-                self.registers.accumulator |= val;
-                self.processor_status.update_zero_neg_flags(self.registers.accumulator);
-            },
-            Opcode::ORAindY => {
-                let addr = self.indirect_y_addr(bus,pipe_byte1, self.registers.y);
-                let val = bus.get_byte_at(addr);
-                self.registers.accumulator |= val;
-                self.processor_status.update_zero_neg_flags(self.registers.accumulator);
-            },
-            Opcode::ORAz => {
-                let addr = self.zero_page_addr(pipe_byte1, 0);
-                let val = bus.get_byte_at(addr);
-                self.registers.accumulator |= val;
-                self.processor_status.update_zero_neg_flags(self.registers.accumulator);
-            },
-            Opcode::ORAzX => {
-                let addr = self.zero_page_addr(pipe_byte1, self.registers.x);
-                let val = bus.get_byte_at(addr);
-                self.registers.accumulator |= val;
-                self.processor_status.update_zero_neg_flags(self.registers.accumulator);
-            },
-            Opcode::ROLabs => {
-                let addr = self.abs_addr(pipe_byte1, pipe_byte2, 0);
-                let val = bus.get_byte_at(addr);
-                bus.set_byte_at(addr, self.rotate_left(val));
-            },
-            Opcode::ROLabsX => {
-                let addr = self.abs_addr(pipe_byte1, pipe_byte2, self.registers.x);
-                let val = bus.get_byte_at(addr);
-                bus.set_byte_at(addr, self.rotate_left(val));
-            },
-            Opcode::ROLacc => {
-                self.registers.accumulator = self.rotate_left(self.registers.accumulator);
-            },
-            Opcode::ROLz => {
-                let addr = self.zero_page_addr(pipe_byte1, 0);
-                let val = bus.get_byte_at(addr);
-                bus.set_byte_at(addr, self.rotate_left(val));
-            },
-            Opcode::ROLzX => {
-                let addr = self.zero_page_addr(pipe_byte1, self.registers.x);
-                let val = bus.get_byte_at(addr);
-                bus.set_byte_at(addr, self.rotate_left(val));
-            },
-            Opcode::RORabs => {
-                let addr = self.abs_addr(pipe_byte1, pipe_byte2, 0);
-                let val = bus.get_byte_at(addr);
-                bus.set_byte_at(addr, self.rotate_right(val));
-            },
-            Opcode::RORabsX => {
-                let addr = self.abs_addr(pipe_byte1, pipe_byte2, self.registers.x);
-                let val = bus.get_byte_at(addr);
-                bus.set_byte_at(addr, self.rotate_right(val));
-            },
-            Opcode::RORacc => {
-                self.registers.accumulator = self.rotate_right(self.registers.accumulator);
-            },
-            Opcode::RORz => {
-                let addr = self.zero_page_addr(pipe_byte1, 0);
-                let val = bus.get_byte_at(addr);
-                bus.set_byte_at(addr, self.rotate_right(val));
-            },
-            Opcode::RORzX => {
-                let addr = self.zero_page_addr(pipe_byte1, self.registers.x);
-                let val = bus.get_byte_at(addr);
-                bus.set_byte_at(addr, self.rotate_right(val));
-            },
-            Opcode::RTI => {
-                let mut status = self.pull_stack(bus) & 0b1100_1111;
-                status |= self.processor_status.as_byte() & 0b0011_0000;
-                self.processor_status = status.into();
+    // Runs one instruction from the given bytes instead of fetching it: nothing
+    // is read at PC and PC only moves if the instruction itself moves it.
+    pub fn execute_raw<T:BusInterface + ?Sized>(&mut self, bus:&mut T, opcode:u8, op1:u8, op2:u8) {
+        self.last_pc_debug = self.registers.program_counter;
+        let _ = self.execute(bus, opcode, op1, op2);
+    }
 
-                let ret_addr_lo = self.pull_stack(bus);
-                let ret_addr_hi =  self.pull_stack(bus);
-                let ret_addr = self.abs_addr(ret_addr_lo, ret_addr_hi, 0);
+    fn execute<T:BusInterface + ?Sized>(&mut self, bus:&mut T, raw_opcode_byte:u8, pipe_byte1:u8, pipe_byte2:u8) -> Result<(), CpuError> {
+        let opcode = Opcode::decode(raw_opcode_byte, self.variant);
+        self.current_opcode = opcode;
 
-                self.registers.program_counter = ret_addr;
-            },
-            Opcode::RTS => {
-                let ret_addr_lo = self.pull_stack(bus);
-                let ret_addr_hi =  self.pull_stack(bus);
-                let ret_addr = self.abs_addr(ret_addr_lo,ret_addr_hi, 1);
-                self.registers.program_counter = ret_addr;
-            },
-            Opcode::SBCabs => {
-                let addr = self.abs_addr(pipe_byte1, pipe_byte2, 0);
-                let val = bus.get_byte_at(addr);
-                self.subtract_with_carry(val);
-            },
-            Opcode::SBCabsX => {
-                let addr = self.abs_addr(pipe_byte1, pipe_byte2, self.registers.x);
-                let val = bus.get_byte_at(addr);
-                self.subtract_with_carry(val);
-            },
-            Opcode::SBCabsY => {
-                let addr = self.abs_addr(pipe_byte1, pipe_byte2, self.registers.y);
-                let val = bus.get_byte_at(addr);
-                self.subtract_with_carry(val);
-            },
-            Opcode::SBCimm => { // immediate
-                self.subtract_with_carry(pipe_byte1);
-            },
-            Opcode::SBCindX => {
-                let addr = self.indirect_x_addr(bus,pipe_byte1, self.registers.x);
-                let val = bus.get_byte_at(addr);
-                self.subtract_with_carry(val);
-            },
-            Opcode::SBCindY => {
-                let addr = self.indirect_y_addr(bus,pipe_byte1, self.registers.y);
-                let val = bus.get_byte_at(addr);
-                self.subtract_with_carry(val);
-            },
-            Opcode::SBCz => {
-                let addr = self.zero_page_addr(pipe_byte1, 0);
-                let val = bus.get_byte_at(addr);
-                self.subtract_with_carry(val);
-            },
-            Opcode::SBCzX => {
-                let addr = self.zero_page_addr(pipe_byte1, self.registers.x);
-                let val = bus.get_byte_at(addr);
-                self.subtract_with_carry(val);
-            },
-            Opcode::STA => {
-                let set_addr = self.abs_addr(pipe_byte1,pipe_byte2, 0);
-                bus.set_byte_at(set_addr, self.registers.accumulator);
-            },
-            Opcode::STAz => {
-                let set_addr = self.zero_page_addr(pipe_byte1,0);
-                bus.set_byte_at(set_addr, self.registers.accumulator);
-            },
-            Opcode::STAzX => {
-                let set_addr = self.zero_page_addr(pipe_byte1,self.registers.x);
-                bus.set_byte_at(set_addr, self.registers.accumulator);
-            },
-            Opcode::STAabsX => { // store accumulator absolute + relative X
-                let set_addr = self.abs_addr(pipe_byte1, pipe_byte2, self.registers.x);
-                bus.set_byte_at(set_addr, self.registers.accumulator);
-            },
-            Opcode::STAay => {
-                let set_addr = self.abs_addr(pipe_byte1,pipe_byte2, self.registers.y);
-                bus.set_byte_at(set_addr, self.registers.accumulator);
-            },
-            Opcode::STAindX => {
-                let addr =  self.indirect_x_addr(bus,pipe_byte1, self.registers.x);
+        self.num_instructions_executed_debug = self.num_instructions_executed_debug.wrapping_add(1);
+        self.total_instructions += 1;
+        self.last_pc_cycles = if self.count_cycles { opcode.cycle_inc() } else { 0 };
+        self.last_page_crossed = false;
+        self.last_branch_penalty = 0;
+        self.last_page_cross_penalty = 0;
+        self.last_opcode_byte = raw_opcode_byte;
+        self.last_operands = (pipe_byte1, pipe_byte2);
+
+        OpcodeTable::<T>::HANDLERS[opcode as usize](self, bus, pipe_byte1, pipe_byte2);
+        let result = match opcode {
+            Opcode::UNREC => Err(CpuError::UnknownOpcode(raw_opcode_byte, self.last_pc_debug)),
+            _ => Ok(()),
+        };
 
-                bus.set_byte_at(addr, self.registers.accumulator);
-            },
-            Opcode::STAindY => {
-                let addr = self.indirect_y_addr(bus,pipe_byte1, self.registers.y);
-                bus.set_byte_at(addr, self.registers.accumulator);
-            },
-            Opcode::STX => {
-                let set_addr = self.abs_addr(pipe_byte1,pipe_byte2, 0);
-                bus.set_byte_at(set_addr, self.registers.x);
-            },
-            Opcode::STXz => {
-                let set_addr = self.zero_page_addr(pipe_byte1,0);
-                bus.set_byte_at(set_addr, self.registers.x);
-            },
-            Opcode::STXzY => {
-                let set_addr = self.zero_page_addr(pipe_byte1,self.registers.y);
-                bus.set_byte_at(set_addr, self.registers.x);
-            },
-            Opcode::STY => {
-                let set_addr = self.abs_addr(pipe_byte1,pipe_byte2, 0);
-                bus.set_byte_at(set_addr, self.registers.y);
-            },
-            Opcode::STYz => {
-                let set_addr = self.zero_page_addr(pipe_byte1,0);
-                bus.set_byte_at(set_addr, self.registers.y);
+        self.total_cycles += self.last_pc_cycles as u64;
+        if self.profiling {
+            self.opcode_histogram[raw_opcode_byte as usize] += 1;
+            if self.count_cycles {
+                self.addressing_mode_cycles[opcode.addressing_mode() as usize] += self.last_pc_cycles as u64;
             }
-            Opcode::STYzX => {
-                let set_addr = self.zero_page_addr(pipe_byte1,self.registers.x);
-                bus.set_byte_at(set_addr, self.registers.y);
-            },
-            Opcode::TXS => { // transfer X to SP
-                self.registers.stack_pointer = self.registers.x;
-            },
-            Opcode::TSX => { // transfer SP to X
-                self.registers.x = self.registers.stack_pointer;
-                self.processor_status.update_zero_neg_flags(self.registers.x);
-            },
-            Opcode::TAX => {
-                self.registers.x = self.registers.accumulator;
-                self.processor_status.update_zero_neg_flags(self.registers.x);
-            },
-            Opcode::TXA => {
-                self.registers.accumulator = self.registers.x;
-                self.processor_status.update_zero_neg_flags(self.registers.accumulator);
-            },
-            Opcode::TAY => {
-                self.registers.y = self.registers.accumulator;
-                self.processor_status.update_zero_neg_flags(self.registers.y);
-            },
-            Opcode::TYA => {
-                self.registers.accumulator = self.registers.y;
-                self.processor_status.update_zero_neg_flags(self.registers.accumulator);
-            },
-            Opcode::PHP => {
-                // possibly required to set bits 4 & 5 when pushing..
-                // self.processor_status.byte |= 0b0011_0000;
-                let mut push_status = self.processor_status.as_byte();
-                push_status |= 0b0011_0000;
-                self.push_stack(bus, push_status);
-            },
-            Opcode::PHA => {
-                self.push_stack(bus, self.registers.accumulator);
-            },
-            Opcode::PLA => {
-                self.registers.accumulator = self.pull_stack(bus);
-                self.processor_status.update_zero_neg_flags(self.registers.accumulator);
-            },
-            Opcode::PLP => {
-                // errata: bflag0 and 1 can not be pulled with PLP
-                // these two bits do not physically exist on the real processor, and always report as 1
-                let mut status_without_bflags = self.pull_stack(bus) & 0b1100_1111;
-                status_without_bflags |= self.processor_status.as_byte() & 0b0011_0000;
-                self.processor_status = status_without_bflags.into();
-            },
-            Opcode::CLC => {
-                self.processor_status.clr_carry();
-            },
-            Opcode::SEC => {
-                self.processor_status.set_carry();
-            }
-            Opcode::CLD => {
-                self.processor_status.clr_decimal();
-            },
-            Opcode::SED => {
-                self.processor_status.set_decimal();
-            }
-            Opcode::CLI => {
-                self.processor_status.clr_interrupt_disable();
-            },
-            Opcode::SEI => {
-                self.processor_status.set_interrupt_disable();
-            },
-            Opcode::CLV => {
-                self.processor_status.clr_overflow();
-            },
-            Opcode::INX => {
-                self.registers.x = self.registers.x.wrapping_add(1);
-                self.processor_status.update_zero_neg_flags(self.registers.x);
+        }
+
+        result
+    }
+
+
+    // Executes one instruction and describes what ran. None when the tick ran
+    // no instruction: it serviced an interrupt or a reset, found the CPU
+    // halted, or stopped at a breakpoint.
+    pub fn step<T:BusInterface + ?Sized>(&mut self, bus:&mut T) -> Option<StepResult> {
+        let instructions = self.total_instructions;
+        self.tick(bus);
+        if self.total_instructions == instructions {
+            return None;
+        }
+        Some(StepResult {
+            pc: self.last_pc_debug,
+            opcode: self.last_opcode_byte,
+            cycles: self.last_pc_cycles,
+            disassembly: Disassembly::new(self.last_pc_debug, self.current_opcode, self.last_operands),
+        })
+    }
+
+    // Monitor status line for the instruction at PC, eg. `LDA $1234,X  ; -> $1236 = $42`.
+    #[cfg(feature = "alloc")]
+    pub fn current_target_description<T:BusPeek + ?Sized>(&self, bus:&T) -> String {
+        let pc = self.registers.program_counter;
+        let opcode = Opcode::decode(bus.peek_byte_at(pc), self.variant);
+        let operands = (bus.peek_byte_at(pc.wrapping_add(1)), bus.peek_byte_at(pc.wrapping_add(2)));
+        let disassembly = Disassembly::new(pc, opcode, operands);
+        match self.peek_effective_address(bus, opcode, operands) {
+            Some(addr) => format!("{}  ; -> ${:04X} = ${:02X}", disassembly, addr, bus.peek_byte_at(addr)),
+            None => format!("{}", disassembly),
+        }
+    }
+
+    // `count` instructions from `start` as (address, text), for a code view.
+    // Walks by instruction length, wrapping past $FFFF.
+    #[cfg(feature = "alloc")]
+    pub fn disassemble_region<T:BusPeek + ?Sized>(&self, bus:&T, start:u16, count:usize) -> Vec<(u16, String)> {
+        let mut addr = start;
+        let mut lines = Vec::with_capacity(count);
+        for _ in 0..count {
+            let opcode = Opcode::decode(bus.peek_byte_at(addr), self.variant);
+            let operands = (bus.peek_byte_at(addr.wrapping_add(1)), bus.peek_byte_at(addr.wrapping_add(2)));
+            let disassembly = Disassembly::new(addr, opcode, operands);
+            lines.push((addr, format!("{}", disassembly)));
+            addr = addr.wrapping_add(disassembly.length());
+        }
+        lines
+    }
+
+    // Address the instruction at `addr` would access with the current
+    // registers, resolved through peeks so neither the CPU nor the bus
+    // changes. None for implied, accumulator, immediate and relative modes.
+    pub fn effective_address<T:BusPeek + ?Sized>(&self, bus:&T, addr:u16) -> Option<u16> {
+        let opcode = Opcode::decode(bus.peek_byte_at(addr), self.variant);
+        let operands = (bus.peek_byte_at(addr.wrapping_add(1)), bus.peek_byte_at(addr.wrapping_add(2)));
+        self.peek_effective_address(bus, opcode, operands)
+    }
+
+    // Bytes occupied by the instruction at `addr`, opcode included.
+    pub fn instruction_length<T:BusPeek + ?Sized>(&self, bus:&T, addr:u16) -> u8 {
+        Opcode::decode(bus.peek_byte_at(addr), self.variant).pc_inc() as u8
+    }
+
+    // Address of the instruction after the one at PC, ignoring where it jumps
+    // or branches: the return address of a JSR, so a step-over target.
+    pub fn next_pc<T:BusPeek + ?Sized>(&self, bus:&T) -> u16 {
+        let pc = self.registers.program_counter;
+        pc.wrapping_add(self.instruction_length(bus, pc) as u16)
+    }
+
+    // Whether the instruction at PC is a JMP or taken branch to itself, the
+    // usual end-of-test trap. A branch's flags can't change while it spins, so
+    // one that isn't taken now never will be.
+    pub fn is_at_trap<T:BusPeek + ?Sized>(&self, bus:&T) -> bool {
+        let pc = self.registers.program_counter;
+        let (b1, b2) = (bus.peek_byte_at(pc.wrapping_add(1)), bus.peek_byte_at(pc.wrapping_add(2)));
+        let status = &self.processor_status;
+        let taken = match Opcode::decode(bus.peek_byte_at(pc), self.variant) {
+            Opcode::JMP => return u16::from_le_bytes([b1, b2]) == pc,
+            Opcode::BPL => !status.negative(),
+            Opcode::BMI => status.negative(),
+            Opcode::BVC => !status.overflow(),
+            Opcode::BVS => status.overflow(),
+            Opcode::BCC => !status.carry(),
+            Opcode::BCS => status.carry(),
+            Opcode::BNE => !status.zero(),
+            Opcode::BEQ => status.zero(),
+            _ => return false,
+        };
+        // offset -2 lands back on the opcode
+        taken && b1 == 0xFE
+    }
+
+    // Mirrors the addressing in tick using peeks; None for modes without a memory operand.
+    fn peek_effective_address<T:BusPeek + ?Sized>(&self, bus:&T, opcode:Opcode, operands:(u8, u8)) -> Option<u16> {
+        let (b1, b2) = operands;
+        let word = u16::from_le_bytes([b1, b2]);
+        let addr = match opcode.addressing_mode() {
+            AddressingMode::Implied | AddressingMode::Accumulator |
+            AddressingMode::Immediate | AddressingMode::Relative => return None,
+            AddressingMode::ZeroPage | AddressingMode::ZeroPageRelative => b1 as u16,
+            AddressingMode::ZeroPageX => zero_page_indexed(b1, self.registers.x),
+            AddressingMode::ZeroPageY => zero_page_indexed(b1, self.registers.y),
+            AddressingMode::Absolute => word,
+            AddressingMode::AbsoluteX => abs_indexed(b1, b2, self.registers.x).0,
+            AddressingMode::AbsoluteY => abs_indexed(b1, b2, self.registers.y).0,
+            AddressingMode::Indirect => {
+                let hi_addr = match self.variant {
+                    Variant::Nmos6502 | Variant::Nes2A03 | Variant::Mos6510 => jmp_indirect_hi_addr(word),
+                    Variant::Cmos65C02 => word.wrapping_add(1),
+                };
+                u16::from_le_bytes([bus.peek_byte_at(word), bus.peek_byte_at(hi_addr)])
+            },
+            AddressingMode::AbsoluteIndirectX => {
+                let pointer = word.wrapping_add(self.registers.x as u16);
+                u16::from_le_bytes([bus.peek_byte_at(pointer), bus.peek_byte_at(pointer.wrapping_add(1))])
+            },
+            AddressingMode::IndirectX => {
+                let zp_addr = zero_page_indexed(b1, self.registers.x);
+                let zp_addr_hi = zero_page_indexed(b1, self.registers.x.wrapping_add(1));
+                u16::from_le_bytes([bus.peek_byte_at(zp_addr), bus.peek_byte_at(zp_addr_hi)])
+            },
+            AddressingMode::IndirectY => {
+                u16::from_le_bytes([bus.peek_byte_at(b1 as u16), bus.peek_byte_at(zero_page_indexed(b1, 1))])
+                    .wrapping_add(self.registers.y as u16)
+            },
+            AddressingMode::ZeroPageIndirect => {
+                u16::from_le_bytes([bus.peek_byte_at(b1 as u16), bus.peek_byte_at(zero_page_indexed(b1, 1))])
             },
-            Opcode::DEX => {
-                self.registers.x = self.registers.x.wrapping_sub(1);
-                self.processor_status.update_zero_neg_flags(self.registers.x);
+        };
+        Some(addr)
+    }
+
+    // The bus fetches an instruction in one call, but on the 6510 any of its
+    // bytes at $0000/$0001 come from the port registers. Then the bytes are
+    // read one at a time, with the operand count taken from the real opcode.
+    fn fetch_instruction<T:BusInterface + ?Sized>(&mut self, bus:&mut T, pc:u16) -> (u8, u8, u8) {
+        let addrs = [0, 1, 2].map(|i| bus.mask_addr(pc.wrapping_add(i)));
+        if addrs.iter().all(|addr| self.io_port_register(*addr).is_none()) {
+            return bus.get_pipelined_bytes(pc, self.variant);
+        }
+        let opcode = self.io_port_read(addrs[0]).unwrap_or_else(|| bus.get_byte_at(addrs[0]));
+        let len = Opcode::decode(opcode, self.variant).pc_inc();
+        let b1 = if len > 1 { self.io_port_read(addrs[1]).unwrap_or_else(|| bus.get_byte_at(addrs[1])) } else { opcode };
+        let b2 = if len > 2 { self.io_port_read(addrs[2]).unwrap_or_else(|| bus.get_byte_at(addrs[2])) } else { b1 };
+        (opcode, b1, b2)
+    }
+
+    // All data accesses go through these two so breakpoints see them.
+    fn read_byte<T:BusInterface + ?Sized>(&mut self, bus:&mut T, addr:u16) -> u8 {
+        #[cfg(feature = "alloc")]
+        if self.has_breakpoint(addr, BreakpointKind::Read) {
+            self.hit_breakpoint = Some(addr);
+        }
+        let addr = bus.mask_addr(addr);
+        let byte = self.io_port_read(addr).unwrap_or_else(|| bus.get_byte_at(addr));
+        bus.on_bus_access(addr, byte, false);
+        byte
+    }
+
+    // Two consecutive reads in one bus call. Not for zero page pointers,
+    // whose high byte wraps within page zero.
+    fn read_word<T:BusInterface + ?Sized>(&mut self, bus:&mut T, addr:u16) -> u16 {
+        let hi_addr = addr.wrapping_add(1);
+        #[cfg(feature = "alloc")]
+        for a in [addr, hi_addr] {
+            if self.has_breakpoint(a, BreakpointKind::Read) {
+                self.hit_breakpoint = Some(a);
             }
-            Opcode::INY => {
-                self.registers.y = self.registers.y.wrapping_add(1);
-                self.processor_status.update_zero_neg_flags(self.registers.y);
-            },
-            Opcode::INCabs => {
-                let addr = self.abs_addr(pipe_byte1,pipe_byte2,0);
-                let val = bus.get_byte_at(addr).wrapping_add(1);
-                self.processor_status.update_zero_neg_flags(val);
-                bus.set_byte_at(addr, val);
-            },
-            Opcode::INCabsx => {
-                let addr = self.abs_addr(pipe_byte1,pipe_byte2,self.registers.x);
-                let val = bus.get_byte_at(addr).wrapping_add(1);
-                self.processor_status.update_zero_neg_flags(val);
-                bus.set_byte_at(addr, val);
-            },
-            Opcode::INCz => {
-                let addr = self.zero_page_addr(pipe_byte1,0);
-                let val = bus.get_byte_at(addr).wrapping_add(1);
-                self.processor_status.update_zero_neg_flags(val);
-                bus.set_byte_at(addr, val);
-            },
-            Opcode::INCzx => { // note: we are supposed to wrap within pages
-                let addr = self.zero_page_addr(pipe_byte1,self.registers.x);
-                let val = bus.get_byte_at(addr).wrapping_add(1);
-                self.processor_status.update_zero_neg_flags(val);
-                bus.set_byte_at(addr, val);
-            },
-            Opcode::DEY => {
-                self.registers.y = self.registers.y.wrapping_sub(1);
-                self.processor_status.update_zero_neg_flags(self.registers.y);
-            },
-            Opcode::BCC => {
-                if !self.processor_status.carry() {
-                    self.last_pc_cycles += 1;
-                    self.branch_by_offset(pipe_byte1);
-                }
-            },
-            Opcode::BCS => {
-                if self.processor_status.carry() {
-                    self.last_pc_cycles += 1;
-                    self.branch_by_offset(pipe_byte1);
-                }
-            },
-            Opcode::BEQ => {
-                if self.processor_status.zero() {
-                    self.last_pc_cycles += 1;
-                    self.branch_by_offset(pipe_byte1);
-                }
-            },
-            Opcode::BNE => {
-                if !self.processor_status.zero() {
-                    self.last_pc_cycles += 1;
-                    self.branch_by_offset(pipe_byte1);
-                }
-            },
-            Opcode::BPL => {
-                if !self.processor_status.negative() {
-                    self.last_pc_cycles += 1;
-                    self.branch_by_offset(pipe_byte1);
-                }
-            },
-            Opcode::BMI => {
-                if self.processor_status.negative() {
-                    self.last_pc_cycles += 1;
-                    self.branch_by_offset(pipe_byte1);
-                }
-            },
-            Opcode::BVC => {
-                if !self.processor_status.overflow() {
-                    self.last_pc_cycles += 1;
-                    self.branch_by_offset(pipe_byte1);
-                }
-            },
-            Opcode::BVS => {
-                if self.processor_status.overflow() {
+        }
+        let (lo_addr, hi_addr) = (bus.mask_addr(addr), bus.mask_addr(hi_addr));
+        let word = match (self.io_port_read(lo_addr), self.io_port_read(hi_addr)) {
+            (None, None) => bus.get_word_at(addr),
+            (lo, hi) => u16::from_le_bytes([
+                lo.unwrap_or_else(|| bus.get_byte_at(lo_addr)),
+                hi.unwrap_or_else(|| bus.get_byte_at(hi_addr)),
+            ]),
+        };
+        let [lo, hi] = word.to_le_bytes();
+        bus.on_bus_access(lo_addr, lo, false);
+        bus.on_bus_access(hi_addr, hi, false);
+        word
+    }
+
+    // The 65C02 fixed the NMOS page wrap (see jmp_indirect_hi_addr) at the
+    // cost of a cycle.
+    fn jmp_indirect_target<T:BusInterface + ?Sized>(&mut self, bus:&mut T, pointer:u16) -> u16 {
+        match self.variant {
+            Variant::Nmos6502 | Variant::Nes2A03 | Variant::Mos6510 => {
+                let lo = self.read_byte(bus, pointer);
+                let hi = self.read_byte(bus, jmp_indirect_hi_addr(pointer));
+                u16::from_le_bytes([lo, hi])
+            },
+            Variant::Cmos65C02 => {
+                if self.count_cycles {
                     self.last_pc_cycles += 1;
-                    self.branch_by_offset(pipe_byte1);
                 }
+                self.read_word(bus, pointer)
             },
-            Opcode::CPX => {
-                self.processor_status.update_flags_with_compare(self.registers.x,pipe_byte1);
-            },
-            Opcode::CPXz => {
-                let get_addr = self.zero_page_addr(pipe_byte1,0);
-                let cmp_val = bus.get_byte_at(get_addr);
-                self.processor_status.update_flags_with_compare(self.registers.x,cmp_val);
-            },
-            Opcode::CPXabs => {
-                let get_addr = self.abs_addr(pipe_byte1,pipe_byte2, 0);
-                let cmp_val = bus.get_byte_at(get_addr);
-                self.processor_status.update_flags_with_compare(self.registers.x, cmp_val);
-            },
-            Opcode::CPY => {
-                self.processor_status.update_flags_with_compare(self.registers.y,pipe_byte1);
-            }
-            Opcode::CPYz => {
-                let get_addr = self.zero_page_addr(pipe_byte1,0);
-                let val = bus.get_byte_at(get_addr);
-                self.processor_status.update_flags_with_compare(self.registers.y,val);
-            },
-            Opcode::CPYabs => {
-                let get_addr = self.abs_addr(pipe_byte1,pipe_byte2, 0);
-                let val = bus.get_byte_at(get_addr);
-                self.processor_status.update_flags_with_compare(self.registers.y, val);
-            },
-            Opcode::CMPabs => {
-                let cmp_addr = self.abs_addr(pipe_byte1,pipe_byte2, 0);
-                let val = bus.get_byte_at(cmp_addr);
-                self.processor_status.update_flags_with_compare(self.registers.accumulator,val);
-            },
-            Opcode::CMPabsx => { 
-                let cmp_addr = self.abs_addr(pipe_byte1,pipe_byte2, self.registers.x);
-                let val = bus.get_byte_at(cmp_addr);
-            
-                self.processor_status.update_flags_with_compare(self.registers.accumulator,val);
-            },
-            Opcode::CMPabsy => { 
-                let cmp_addr = self.abs_addr(pipe_byte1,pipe_byte2, self.registers.y);
-                let cmp_val = bus.get_byte_at(cmp_addr);
-            
-                self.processor_status.update_flags_with_compare(self.registers.accumulator,cmp_val);
-            },
-            Opcode::CMPindX => {
-                let addr =  self.indirect_x_addr(bus,pipe_byte1, self.registers.x);
+        }
+    }
 
-                let cmp_val = bus.get_byte_at(addr);
-                self.processor_status.update_flags_with_compare(self.registers.accumulator, cmp_val);
-            },
-            Opcode::CMPindY => {
-                let addr = self.indirect_y_addr(bus,pipe_byte1, self.registers.y);
-                let cmp_val = bus.get_byte_at(addr);
-                self.processor_status.update_flags_with_compare(self.registers.accumulator, cmp_val);
-            },
-            Opcode::CMPimm => {
-                self.processor_status.update_flags_with_compare(self.registers.accumulator,pipe_byte1);
-            },
-            Opcode::CMPz => {
-                let cmp_addr = self.zero_page_addr(pipe_byte1,0);
-                let val = bus.get_byte_at(cmp_addr);
-                self.processor_status.update_flags_with_compare(self.registers.accumulator, val);
-            },
-            Opcode::CMPzX => {
-                let cmp_addr = self.zero_page_addr(pipe_byte1,self.registers.x);
-                let val = bus.get_byte_at(cmp_addr);
-                self.processor_status.update_flags_with_compare(self.registers.accumulator, val);
-            },
-            Opcode::BRK => {
-                self.push_stack_interrupt(InterruptType::BRK, bus);
-                self.break_flag_ext_debug = true;
-            }, 
-            Opcode::NOP => (),
-            // Begin "illegal" opcodes
-            Opcode::NOPi0 => (),
-            Opcode::NOPim => (),
-            Opcode::NOPim2 => (),
-            Opcode::NOPim3 => (),
-            Opcode::NOPim4 => (),
-            Opcode::NOPim5 => (),
-            Opcode::NOPim6 => (),
-            Opcode::NOPi2 => (),
-            Opcode::NOPi3 => (),
-            Opcode::NOPi4 => (),
-            Opcode::NOPi5 => (),
-            Opcode::NOPz0 => (),
-            Opcode::NOPz1 => (),
-            Opcode::NOPz2 => (),
-            Opcode::NOPzX0 => (),
-            Opcode::NOPzX1 => (),
-            Opcode::NOPzX2 => (),
-            Opcode::NOPzX3 => (),
-            Opcode::NOPzX4 => (),
-            Opcode::NOPzX5 => (),
-            Opcode::NOPabs => (),
-            Opcode::NOPabsX0 => (),
-            Opcode::NOPabsX1 => (),
-            Opcode::NOPabsX2 => (),
-            Opcode::NOPabsX3 => (),
-            Opcode::NOPabsX4 => (),
-            Opcode::NOPabsX5 => (),
-            Opcode::UNREC => { 
-                self.uncaught_opcode_debug = Some(raw_opcode_byte);
-            } // "Illegal" implied NOP (here for debug)
-        }
-
-    }
-
-
-    fn indirect_x_addr<T:BusInterface>(&mut self, bus:&mut T, byte:u8, x:u8) -> u16 {
-        let zp_addr = self.zero_page_addr(byte,x);
-        u16::from_le_bytes([bus.get_byte_at(zp_addr),bus.get_byte_at(zp_addr.wrapping_add(1))])
-    }
-
-    fn indirect_y_addr<T:BusInterface>(&mut self, bus:&mut T, byte:u8, y:u8) -> u16 {
-        let zp_addr = self.zero_page_addr(byte,0);
-        if (zp_addr as u8).overflowing_add(y).1 {
-            self.last_pc_cycles += 1
-        }
-        let addr = self.abs_addr(bus.get_byte_at(zp_addr),bus.get_byte_at(zp_addr.wrapping_add(1)), 0);
-        addr.wrapping_add(y as u16)
-    }
-
-    fn zero_page_addr(&mut self, index:u8, off:u8) -> u16 {
-        if index.overflowing_add(off).1 {
-            self.last_pc_cycles += 1;
+    fn write_byte<T:BusInterface + ?Sized>(&mut self, bus:&mut T, addr:u16, byte:u8) {
+        #[cfg(feature = "alloc")]
+        if self.has_breakpoint(addr, BreakpointKind::Write) {
+            self.hit_breakpoint = Some(addr);
+        }
+        #[cfg(feature = "alloc")]
+        if self.write_watches.iter().any(|range| range.contains(&addr)) {
+            self.write_hits.push((addr, byte, self.last_pc_debug));
         }
-        (index.wrapping_add(off)) as u16
+        let addr = bus.mask_addr(addr);
+        match self.io_port_register(addr) {
+            Some(0) => self.port_ddr = byte,
+            Some(_) => self.port_data = byte,
+            None => bus.set_byte_at(addr, byte),
+        }
+        bus.on_bus_access(addr, byte, true);
     }
 
-    fn abs_addr(&mut self, lo:u8,hi:u8,off:u8) -> u16 {
-        let addr = u16::from_le_bytes([lo,hi]).wrapping_add(off as u16);
-        if (addr as u8).overflowing_add(off).1 {
-            self.last_pc_cycles += 1
+    // $0000/$0001 on the 6510 are its own port registers, not the bus, for
+    // every read and write: data, instruction fetches and vectors alike.
+    fn io_port_register(&self, addr:u16) -> Option<u16> {
+        match (self.variant, addr) {
+            (Variant::Mos6510, 0x0000..=0x0001) => Some(addr),
+            _ => None,
         }
-        addr
     }
 
-    fn add_with_carry(&mut self, byte:u8) {
-        let c = match self.processor_status.carry() {
-            false => 0,
-            true => 1
-        };
+    // What a read of `addr` gives when it is a port register.
+    fn io_port_read(&self, addr:u16) -> Option<u8> {
+        match self.io_port_register(addr) {
+            Some(0) => Some(self.port_ddr),
+            Some(_) => Some(self.io_port()),
+            None => None,
+        }
+    }
 
-        let mut uresult = self.registers.accumulator.wrapping_add(byte); 
+    // The 6510 port's pin levels, as read at $0001: outputs drive the data
+    // register's bits, inputs float high. Only bits 0-5 are pins.
+    pub fn io_port(&self) -> u8 {
+        (self.port_data | !self.port_ddr) & 0x3F
+    }
 
-        if !self.processor_status.decimal() {
-            // set carry based on unsigned math
-            if (self.registers.accumulator > uresult) || (byte > uresult) {
-                self.processor_status.set_carry();
-            } else {
-                if uresult == 0xFF && c == 1 { // stupid edge case
-                    self.processor_status.set_carry();
-                } else {
-                    self.processor_status.clr_carry();
-                }
-            }
+    #[cfg(feature = "alloc")]
+    pub fn add_breakpoint(&mut self, addr:u16, kind:BreakpointKind) {
+        if !self.has_breakpoint(addr, kind) {
+            self.breakpoints.push((addr, kind));
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn remove_breakpoint(&mut self, addr:u16, kind:BreakpointKind) {
+        self.breakpoints.retain(|bp| *bp != (addr, kind));
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    // Record every write into `range`, eg. to spot self-modifying code; see
+    // take_write_hits. Unlike a Write breakpoint, nothing stops.
+    #[cfg(feature = "alloc")]
+    pub fn watch_write(&mut self, range:Range<u16>) {
+        self.write_watches.push(range);
+    }
 
-            uresult = uresult.wrapping_add(c);
+    #[cfg(feature = "alloc")]
+    pub fn clear_write_watches(&mut self) {
+        self.write_watches.clear();
+    }
+
+    // Watched writes since the last call, oldest first, as
+    // (address, value, PC of the writing instruction).
+    #[cfg(feature = "alloc")]
+    pub fn take_write_hits(&mut self) -> Vec<(u16, u8, u16)> {
+        core::mem::take(&mut self.write_hits)
+    }
+
+    #[cfg(feature = "alloc")]
+    fn has_breakpoint(&self, addr:u16, kind:BreakpointKind) -> bool {
+        !self.breakpoints.is_empty() && self.breakpoints.contains(&(addr, kind))
+    }
+
+    // Checks each instruction against `lines` before it runs, recording the
+    // first mismatch in `divergence`. Cycles are compared relative to the
+    // first line, so a log starting at CYC:7 lines up with any start point.
+    // B and the unused bit are ignored in P.
+    #[cfg(feature = "alloc")]
+    pub fn attach_golden_log(&mut self, lines:Vec<GoldenLine>) {
+        self.golden_log = lines;
+        self.golden_index = 0;
+        self.golden_cycle_base = self.total_cycles;
+        self.divergence = None;
+    }
+
+    #[cfg(feature = "alloc")]
+    fn check_golden_line(&mut self) {
+        if self.divergence.is_some() {
+            return;
+        }
+        let Some(line) = self.golden_log.get(self.golden_index) else {
+            return;
+        };
+        let expected_cycles = line.cycles.wrapping_sub(self.golden_log[0].cycles);
+        let cycles = self.total_cycles.wrapping_sub(self.golden_cycle_base);
+        let status = self.processor_status.as_byte();
+        let mismatch = if line.pc != self.registers.program_counter {
+            Some(format!("PC expected ${:04X}, got ${:04X}", line.pc, self.registers.program_counter))
+        } else if line.a != self.registers.accumulator {
+            Some(format!("A expected ${:02X}, got ${:02X}", line.a, self.registers.accumulator))
+        } else if line.x != self.registers.x {
+            Some(format!("X expected ${:02X}, got ${:02X}", line.x, self.registers.x))
+        } else if line.y != self.registers.y {
+            Some(format!("Y expected ${:02X}, got ${:02X}", line.y, self.registers.y))
+        } else if (line.p ^ status) & !STATUS_UNUSED_BITS != 0 {
+            Some(format!("P expected ${:02X}, got ${:02X}", line.p, status))
+        } else if line.sp != self.registers.stack_pointer {
+            Some(format!("SP expected ${:02X}, got ${:02X}", line.sp, self.registers.stack_pointer))
+        } else if expected_cycles != cycles {
+            Some(format!("CYC expected {}, got {}", expected_cycles, cycles))
         } else {
-            let a_lo = self.registers.accumulator & 0xF;
-            let a_hi = self.registers.accumulator >> 4;
-            let op_lo = byte & 0xF;
-            let op_hi = byte >> 4;
-
-            let lo_result = a_lo + op_lo + c;
-            let c = if lo_result > 9 { 1 } else { 0 };
-            let hi_result = a_hi + op_hi + c;
-            
-            if hi_result > 9 {
-                self.processor_status.set_carry();
-            } else {
-                self.processor_status.clr_carry();
-            }
+            None
+        };
+        self.divergence = mismatch.map(|msg| (self.golden_index, format!("${:04X}: {}", line.pc, msg)));
+        self.golden_index += 1;
+    }
+
+    // The pointer's high byte wraps within page zero: ($FF,X) with X=0 reads $FF then $00.
+    fn indirect_x_addr<T:BusInterface + ?Sized>(&mut self, bus:&mut T, byte:u8, x:u8) -> u16 {
+        let zp_addr = zero_page_indexed(byte, x);
+        let zp_addr_hi = zero_page_indexed(byte, x.wrapping_add(1));
+        u16::from_le_bytes([self.read_byte(bus, zp_addr),self.read_byte(bus, zp_addr_hi)])
+    }
+
+    fn indirect_y_write_addr<T:BusInterface + ?Sized>(&mut self, bus:&mut T, byte:u8, y:u8) -> u16 {
+        let base = self.zero_page_pointer(bus, byte);
+        self.indexed_write_addr(bus, base, y)
+    }
+
+    fn indirect_y_read_addr<T:BusInterface + ?Sized>(&mut self, bus:&mut T, byte:u8, y:u8) -> u16 {
+        let base = self.zero_page_pointer(bus, byte);
+        self.indexed_read_addr(bus, base, y)
+    }
+
+    // The pointer at `byte` for (zp),Y and the 65C02's (zp). As with (zp,X),
+    // its high byte wraps within page zero.
+    fn zero_page_pointer<T:BusInterface + ?Sized>(&mut self, bus:&mut T, byte:u8) -> u16 {
+        let lo = self.read_byte(bus, byte as u16);
+        let hi = self.read_byte(bus, zero_page_indexed(byte, 1));
+        u16::from_le_bytes([lo, hi])
+    }
 
-            uresult = (lo_result%10) | ((hi_result%10) << 4);
+    fn abs_indexed_read_addr<T:BusInterface + ?Sized>(&mut self, bus:&mut T, lo:u8, hi:u8, index:u8) -> u16 {
+        self.indexed_read_addr(bus, u16::from_le_bytes([lo, hi]), index)
+    }
+
+    // An indexed read that crosses a page first reads from the address with
+    // the high byte not yet fixed up, costing the extra cycle.
+    fn indexed_read_addr<T:BusInterface + ?Sized>(&mut self, bus:&mut T, base:u16, index:u8) -> u16 {
+        let (addr, crossed) = indexed(base, index);
+        if crossed {
+            self.page_cross_penalty(true);
+            self.read_byte(bus, (base & 0xFF00) | (addr & 0x00FF));
         }
+        addr
+    }
 
-        self.processor_status.clr_overflow();
+    fn abs_indexed_write_addr<T:BusInterface + ?Sized>(&mut self, bus:&mut T, lo:u8, hi:u8, index:u8) -> u16 {
+        self.indexed_write_addr(bus, u16::from_le_bytes([lo, hi]), index)
+    }
 
-        // if 7 bit of acc and pipe are the same,
-        // they are either both neg or both pos
-        // so therefore some risk of overflow
-        if (self.registers.accumulator & 0b1000_0000) == (byte & 0b1000_0000) {
-            // if the sign bit of the result does not match,
-            // we overflowed.
-            if (uresult & 0b1000_0000) != (byte & 0b1000_0000) {
-                self.processor_status.set_overflow();
+    // Stores and read-modify-writes always spend the fix-up cycle, reading
+    // from the unfixed address even when no page is crossed, so there's no
+    // page-cross penalty.
+    fn indexed_write_addr<T:BusInterface + ?Sized>(&mut self, bus:&mut T, base:u16, index:u8) -> u16 {
+        let (addr, _) = indexed(base, index);
+        self.read_byte(bus, (base & 0xFF00) | (addr & 0x00FF));
+        addr
+    }
+
+    fn page_cross_penalty(&mut self, crossed:bool) {
+        if crossed {
+            self.last_page_crossed = true;
+            if self.count_cycles {
+                self.last_pc_cycles += 1;
+                self.last_page_cross_penalty += 1;
             }
         }
+    }
 
-        // finally we can just set the result as the unsigned version
-        self.registers.accumulator = uresult;
-
-        self.processor_status.update_zero_neg_flags(self.registers.accumulator);
+    // D set on a variant that has BCD, see Variant::has_decimal_mode.
+    fn decimal_mode(&self) -> bool {
+        self.processor_status.decimal() && self.variant.has_decimal_mode()
     }
 
-    fn subtract_with_carry(&mut self, byte:u8) {
-        if !self.processor_status.decimal() {
-            let inv_byte = !byte;
-            self.add_with_carry(inv_byte); // maybe? lol
+    fn add_with_carry(&mut self, byte:u8) {
+        if self.decimal_mode() {
+            self.add_with_carry_decimal(byte);
             return;
         }
-        
-        // decimal sbc
-        let mut c = match self.processor_status.carry() {
-            false => 1,
-            true => 0
-        };
-
-        let a_lo = self.registers.accumulator & 0xF;
-        let a_hi = self.registers.accumulator >> 4;
-        let op_lo = byte & 0xF;
-        let op_hi = byte >> 4;
-
-        let mut lo_result = a_lo.wrapping_sub(op_lo + c);
-        if lo_result > 10 {
-            // wrapped under
-            c = 1;
-            lo_result = lo_result.wrapping_add(10);
+
+        let acc = self.registers.accumulator;
+        let sum = acc as u16 + byte as u16 + self.processor_status.carry() as u16;
+        let result = sum as u8;
+
+        if sum > 0xFF {
+            self.processor_status.set_carry();
         } else {
-            c = 0;
+            self.processor_status.clr_carry();
         }
 
-        let mut hi_result = a_hi.wrapping_sub(op_hi + c);
-        if hi_result > 10 {
-            self.processor_status.clr_carry();
-            hi_result = hi_result.wrapping_add(10);
+        // Overflow when both inputs have the same sign and the result doesn't.
+        if (acc ^ result) & (byte ^ result) & 0b1000_0000 != 0 {
+            self.processor_status.set_overflow();
+        } else {
+            self.processor_status.clr_overflow();
+        }
+
+        self.registers.accumulator = result;
+        self.processor_status.update_zero_neg_flags(result);
+    }
+
+    // NMOS decimal ADC, valid for non-BCD operands too. Z comes from the binary
+    // sum, N and V from the result before the high nibble is adjusted.
+    fn add_with_carry_decimal(&mut self, byte:u8) {
+        let acc = self.registers.accumulator as u16;
+        let op = byte as u16;
+        let c = self.processor_status.carry() as u16;
+
+        let mut lo = (acc & 0x0F) + (op & 0x0F) + c;
+        if lo >= 0x0A {
+            lo = ((lo + 0x06) & 0x0F) + 0x10;
+        }
+        let mut result = (acc & 0xF0) + (op & 0xF0) + lo;
+
+        self.processor_status.update_zero_neg_flags(result as u8);
+        if (acc + op + c) as u8 == 0 {
+            self.processor_status.set_zero();
         } else {
+            self.processor_status.clr_zero();
+        }
+        if !(acc ^ op) & (acc ^ result) & 0x80 != 0 {
+            self.processor_status.set_overflow();
+        } else {
+            self.processor_status.clr_overflow();
+        }
+
+        if result >= 0xA0 {
+            result += 0x60;
+        }
+        if result >= 0x100 {
             self.processor_status.set_carry();
+        } else {
+            self.processor_status.clr_carry();
         }
 
-        let uresult = lo_result | hi_result.checked_shl(4).unwrap();
+        self.registers.accumulator = result as u8;
+    }
 
-        self.processor_status.clr_overflow();
-        if (self.registers.accumulator & 0b1000_0000) == (byte & 0b1000_0000) {
-            // if the sign bit of the result does not match,
-            // we overflowed.
-            if (uresult & 0b1000_0000) != (byte & 0b1000_0000) {
-                self.processor_status.set_overflow();
-            }
+    fn subtract_with_carry(&mut self, byte:u8) {
+        if !self.decimal_mode() {
+            let inv_byte = !byte;
+            self.add_with_carry(inv_byte);
+            return;
+        }
+
+        // decimal sbc: every flag is set as in binary mode, only A differs
+        let acc = self.registers.accumulator as i16;
+        let op = byte as i16;
+        let c = self.processor_status.carry() as i16;
+
+        self.processor_status.clr_decimal();
+        self.add_with_carry(!byte);
+        self.processor_status.set_decimal();
+
+        let mut lo = (acc & 0x0F) - (op & 0x0F) + c - 1;
+        if lo < 0 {
+            lo = ((lo - 0x06) & 0x0F) - 0x10;
+        }
+        let mut result = (acc & 0xF0) - (op & 0xF0) + lo;
+        if result < 0 {
+            result -= 0x60;
         }
 
-        self.processor_status.update_zero_neg_flags(uresult);
-        self.registers.accumulator = uresult;
+        self.registers.accumulator = result as u8;
     }
 
+    // A taken branch costs a cycle, and another if the target is on a
+    // different page from the next instruction.
     fn branch_by_offset(&mut self, byte:u8) {
+        if self.count_cycles {
+            self.last_pc_cycles += 1;
+            self.last_branch_penalty = 1;
+        }
         let signed_byte = byte as i8;
         let jmp_addr = self.registers.program_counter.wrapping_add_signed(signed_byte as i16);
+        let page_crossed = jmp_addr & 0xFF00 != self.registers.program_counter & 0xFF00;
+        self.page_cross_penalty(page_crossed);
         self.registers.program_counter = jmp_addr;
+        // quirk: without a page cross the extra cycle doesn't poll, so an
+        // interrupt raised during the branch waits one more instruction
+        if !page_crossed {
+            self.branch_polled_lines = Some((self.irq, self.nmi));
+        }
     }
 
-    fn push_stack<T:BusInterface>(&mut self, mem:&mut T, byte:u8) {
-        let set_addr = self.abs_addr(self.registers.stack_pointer, 0x01, 0);
-        mem.set_byte_at(set_addr, byte);
+    fn push_stack<T:BusInterface + ?Sized>(&mut self, mem:&mut T, byte:u8) {
+        if self.registers.stack_pointer == 0x00 {
+            self.record_stack_fault(StackFault::Overflow(self.last_pc_debug));
+        }
+        let set_addr = u16::from_le_bytes([self.registers.stack_pointer, self.stack_page()]);
+        self.write_byte(mem, set_addr, byte);
+        mem.on_stack_op(self.registers.stack_pointer, byte, true);
         self.registers.stack_pointer = self.registers.stack_pointer.wrapping_sub(1);
+        if self.extended_stack && self.registers.stack_pointer == 0xFF {
+            self.extended_stack_page = self.extended_stack_page.wrapping_sub(1);
+        }
     }
 
-    fn pull_stack<T:BusInterface>(&mut self, mem:&mut T) -> u8 {
+    fn pull_stack<T:BusInterface + ?Sized>(&mut self, mem:&mut T) -> u8 {
+        if self.registers.stack_pointer == 0xFF {
+            self.record_stack_fault(StackFault::Underflow(self.last_pc_debug));
+        }
         self.registers.stack_pointer = self.registers.stack_pointer.wrapping_add(1);
-        let get_addr = self.abs_addr(self.registers.stack_pointer, 0x01, 0);
-        mem.get_byte_at(get_addr)
+        if self.extended_stack && self.registers.stack_pointer == 0x00 {
+            self.extended_stack_page = self.extended_stack_page.wrapping_add(1);
+        }
+        let get_addr = u16::from_le_bytes([self.registers.stack_pointer, self.stack_page()]);
+        let byte = self.read_byte(mem, get_addr);
+        mem.on_stack_op(self.registers.stack_pointer, byte, false);
+        byte
+    }
+
+    // Keeps the first fault until it is taken.
+    fn record_stack_fault(&mut self, fault:StackFault) {
+        if self.stack_guard && self.stack_fault.is_none() {
+            self.stack_fault = Some(fault);
+        }
+    }
+
+    fn stack_page(&self) -> u8 {
+        if self.extended_stack {
+            self.extended_stack_page
+        } else {
+            0x01
+        }
     }
 
     // This is a weird test.
@@ -1047,6 +1105,22 @@ impl Nmos6502 {
         } else {
             self.processor_status.clr_overflow();
         }
+        self.test_bits(val);
+    }
+
+    // 65C02 TSB/TRB: Z from A AND the operand, then set/clear A's bits in it.
+    // N and V are left alone, unlike BIT.
+    fn test_and_set_bits(&mut self, val:u8) -> u8 {
+        self.test_bits(val);
+        val | self.registers.accumulator
+    }
+
+    fn test_and_reset_bits(&mut self, val:u8) -> u8 {
+        self.test_bits(val);
+        val & !self.registers.accumulator
+    }
+
+    fn test_bits(&mut self, val:u8) {
         if (val & self.registers.accumulator) == 0 {
             self.processor_status.set_zero();
         } else {
@@ -1054,6 +1128,32 @@ impl Nmos6502 {
         }
     }
 
+    // NMOS RMW opcodes write the unmodified value back before the result;
+    // I/O registers that react to writes see both. The 65C02 reads the
+    // address again instead, so only the result is written.
+    fn read_modify_write<T:BusInterface + ?Sized>(&mut self, bus:&mut T, addr:u16, op:fn(&mut Self, u8) -> u8) {
+        let val = self.read_byte(bus, addr);
+        if self.variant == Variant::Cmos65C02 {
+            self.read_byte(bus, addr);
+        } else {
+            self.write_byte(bus, addr, val);
+        }
+        let result = op(self, val);
+        self.write_byte(bus, addr, result);
+    }
+
+    fn increment(&mut self, val:u8) -> u8 {
+        let result = val.wrapping_add(1);
+        self.processor_status.update_zero_neg_flags(result);
+        result
+    }
+
+    fn decrement(&mut self, val:u8) -> u8 {
+        let result = val.wrapping_sub(1);
+        self.processor_status.update_zero_neg_flags(result);
+        result
+    }
+
     fn arithmetic_shift_left(&mut self, val:u8) -> u8 {
         if (val & 0b1000_0000) > 0 {
             self.processor_status.set_carry();
@@ -1090,11 +1190,78 @@ impl Nmos6502 {
             self.processor_status.clr_carry();
         }
 
-        let result = val.checked_shl(1).unwrap() + c;
+        let result = val.checked_shl(1).unwrap() | c;
         self.processor_status.update_zero_neg_flags(result);
         result
     }
 
+    // The interrupt serviced since the last call, for clearing the source's
+    // pending flag. A BRK or IRQ hijacked by an NMI reports Nmi.
+    pub fn take_interrupt_ack(&mut self) -> Option<InterruptKind> {
+        self.last_interrupt.take()
+    }
+
+    pub fn irq_pending(&self) -> bool {
+        self.irq
+    }
+
+    pub fn nmi_pending(&self) -> bool {
+        self.nmi
+    }
+
+    // The I flag as it stands; the poll after CLI/SEI/PLP still sees the old
+    // value, see polled_interrupt_disable.
+    pub fn interrupts_enabled(&self) -> bool {
+        !self.processor_status.interrupt_disable()
+    }
+
+    // Assert the IRQ/NMI line once `in_cycles` more cycles have run. The line
+    // is raised at the first instruction boundary at or past that cycle, and
+    // left for the caller to clear as with `irq`/`nmi`. A scheduled NMI that
+    // lands early enough in a BRK or IRQ sequence hijacks it instead, see
+    // push_stack_interrupt.
+    pub fn schedule_irq(&mut self, in_cycles:u64) {
+        self.irq_at = Some(self.total_cycles + in_cycles);
+    }
+
+    pub fn schedule_nmi(&mut self, in_cycles:u64) {
+        self.nmi_at = Some(self.total_cycles + in_cycles);
+    }
+
+    // Runs up to `n` ticks, stopping early if the CPU halts, and returns the
+    // cycles they took.
+    pub fn step_n<T:BusInterface + ?Sized>(&mut self, bus:&mut T, n:u32) -> u64 {
+        let start = self.total_cycles;
+        for _ in 0..n {
+            if self.halted {
+                break;
+            }
+            self.tick(bus);
+        }
+        self.total_cycles - start
+    }
+
+    // Ticks until `stop` returns true (checked before each instruction), the CPU
+    // halts, or at least `max_cycles` have elapsed. Ticks that report no cycles
+    // (a latched reset, count_cycles off) count as one so the cap still holds.
+    pub fn run_until<T:BusInterface + ?Sized, F:FnMut(&Nmos6502) -> bool>(&mut self, bus:&mut T, max_cycles:u64, mut stop:F) -> RunOutcome {
+        let mut elapsed:u64 = 0;
+        loop {
+            if stop(self) {
+                return RunOutcome::Stopped;
+            }
+            if self.halted {
+                return RunOutcome::Halted;
+            }
+            if elapsed >= max_cycles {
+                return RunOutcome::CycleLimit;
+            }
+            let before = self.total_cycles;
+            self.tick(bus);
+            elapsed += (self.total_cycles - before).max(1);
+        }
+    }
+
     fn rotate_right(&mut self, val:u8) -> u8 {
         let c = match self.processor_status.carry() {
             false => 0,
@@ -1107,7 +1274,7 @@ impl Nmos6502 {
             self.processor_status.clr_carry();
         }
 
-        let result = val.checked_shr(1).unwrap() + c;
+        let result = val.checked_shr(1).unwrap() | c;
         self.processor_status.update_zero_neg_flags(result);
         result
     }
@@ -1125,28 +1292,877 @@ impl Nmos6502 {
         self.registers.x
     }
 
+    pub fn set_x(&mut self, val:u8) {
+        self.registers.x = val;
+    }
+
     pub fn get_y(&self) -> u8 {
         self.registers.y
     }
 
+    pub fn set_y(&mut self, val:u8) {
+        self.registers.y = val;
+    }
+
     pub fn get_a(&self) -> u8 {
         self.registers.accumulator
     }
+
+    pub fn set_a(&mut self, val:u8) {
+        self.registers.accumulator = val;
+    }
     
+    // Bits 4 and 5 always read as set. PHP and BRK push exactly this byte,
+    // IRQ and NMI push it with bit 4 clear.
     pub fn get_status(&self) -> u8 {
         self.processor_status.as_byte()
     }
 
+    // Bits 4 and 5 are ignored, as with PLP.
+    pub fn set_status(&mut self, val:u8) {
+        self.processor_status = (val | STATUS_UNUSED_BITS).into();
+    }
+
+    pub fn get_flags(&self) -> StatusFlags {
+        self.processor_status.flags()
+    }
+
+    pub fn set_carry_flag(&mut self, val:bool) {
+        if val {
+            self.processor_status.set_carry();
+        } else {
+            self.processor_status.clr_carry();
+        }
+    }
+
+    pub fn set_zero_flag(&mut self, val:bool) {
+        if val {
+            self.processor_status.set_zero();
+        } else {
+            self.processor_status.clr_zero();
+        }
+    }
+
+    pub fn set_interrupt_disable_flag(&mut self, val:bool) {
+        if val {
+            self.processor_status.set_interrupt_disable();
+        } else {
+            self.processor_status.clr_interrupt_disable();
+        }
+    }
+
+    pub fn set_decimal_flag(&mut self, val:bool) {
+        if val {
+            self.processor_status.set_decimal();
+        } else {
+            self.processor_status.clr_decimal();
+        }
+    }
+
+    pub fn set_overflow_flag(&mut self, val:bool) {
+        if val {
+            self.processor_status.set_overflow();
+        } else {
+            self.processor_status.clr_overflow();
+        }
+    }
+
+    pub fn set_negative_flag(&mut self, val:bool) {
+        if val {
+            self.processor_status.set_negative();
+        } else {
+            self.processor_status.clr_negative();
+        }
+    }
+
+    // The byte fetched, even when it decoded to UNREC.
     pub fn get_opcode(&self) -> u8 {
-        self.current_opcode as u8
+        self.last_opcode_byte
+    }
+
+    // Whether the last instruction was an undocumented opcode, counting ones
+    // this core doesn't recognize; see Opcode::is_illegal.
+    pub fn last_was_illegal(&self) -> bool {
+        self.current_opcode.is_illegal()
+    }
+
+    // Mnemonic of the last instruction, eg. "LDA", without running the
+    // disassembler ("???" if unrecognized). Pairs with last_pc_debug and
+    // last_operands for a trace.
+    pub fn last_mnemonic(&self) -> &'static str {
+        self.current_opcode.mnemonic()
+    }
+
+    // Operand bytes fetched with the last instruction. Only the first
+    // `last_operand_count` belong to it, the rest are pipeline leftovers.
+    pub fn last_operands(&self) -> (u8, u8) {
+        self.last_operands
+    }
+
+    pub fn last_operand_count(&self) -> u8 {
+        self.current_opcode.pc_inc() as u8 - 1
     }
 
     pub fn get_stack_pointer(&self) -> u8 {
         self.registers.stack_pointer
     }
 
+    // Also puts an extended stack back on page 1.
+    pub fn set_stack_pointer(&mut self, val:u8) {
+        self.registers.stack_pointer = val;
+        self.extended_stack_page = 0x01;
+    }
+
+    // SP as a page 1 address, eg. $01FD. See get_extended_stack_pointer for
+    // the page extended_stack is actually using.
+    pub fn stack_address(&self) -> u16 {
+        0x0100 | self.registers.stack_pointer as u16
+    }
+
+    // Bytes in use below $01FF, for a stack usage gauge.
+    pub fn stack_depth(&self) -> u8 {
+        0xFF - self.registers.stack_pointer
+    }
+
+    // Snapshot of the last executed instruction, for golden trace comparison.
+    pub fn instruction_record(&self) -> InstructionRecord {
+        InstructionRecord {
+            program_counter: self.last_pc_debug,
+            opcode: self.get_opcode(),
+            accumulator: self.registers.accumulator,
+            x: self.registers.x,
+            y: self.registers.y,
+            stack_pointer: self.registers.stack_pointer,
+            status: self.processor_status.as_byte(),
+            cycles: self.last_pc_cycles,
+        }
+    }
+
+    // PC, A, X, Y, SP and status packed into one value, for cheap lockstep
+    // comparison of two runs. The registers fit in 56 bits, so this is
+    // lossless: equal values mean equal registers.
+    pub fn state_hash(&self) -> u64 {
+        let [pc_lo, pc_hi] = self.registers.program_counter.to_le_bytes();
+        u64::from_le_bytes([
+            pc_lo, pc_hi,
+            self.registers.accumulator,
+            self.registers.x,
+            self.registers.y,
+            self.registers.stack_pointer,
+            self.processor_status.as_byte(),
+            0,
+        ])
+    }
+
+    // Registers and interrupt/halt lines in a fixed little-endian layout (see
+    // STATE_LEN), for bug reports and fixtures. Memory, counters, variant and
+    // debug settings aren't included.
+    pub fn to_bytes(&self) -> [u8; STATE_LEN] {
+        let [pc_lo, pc_hi] = self.registers.program_counter.to_le_bytes();
+        let flags = self.irq as u8 | (self.nmi as u8) << 1 | (self.halted as u8) << 2;
+        [
+            STATE_VERSION,
+            pc_lo, pc_hi,
+            self.registers.accumulator,
+            self.registers.x,
+            self.registers.y,
+            self.registers.stack_pointer,
+            self.processor_status.as_byte(),
+            flags,
+        ]
+    }
+
+    // A new CPU in the state to_bytes saved. Bytes past STATE_LEN are ignored;
+    // a restored halt has no halt_reason.
+    pub fn from_bytes(bytes:&[u8]) -> Result<Self, ParseError> {
+        let Some(state) = bytes.get(..STATE_LEN) else {
+            return Err(ParseError::Truncated(bytes.len()));
+        };
+        if state[0] != STATE_VERSION {
+            return Err(ParseError::UnsupportedVersion(state[0]));
+        }
+        let pc = u16::from_le_bytes([state[1], state[2]]);
+        let mut cpu = Nmos6502::with_state(pc, state[3], state[4], state[5], state[6], state[7]);
+        cpu.irq = state[8] & 0b001 != 0;
+        cpu.nmi = state[8] & 0b010 != 0;
+        cpu.halted = state[8] & 0b100 != 0;
+        Ok(cpu)
+    }
+
+    // Cycles spent per addressing mode while `profiling` is set,
+    // indexed by `AddressingMode as usize`.
+    pub fn cycles_by_addressing_mode(&self) -> &[u64; AddressingMode::COUNT] {
+        &self.addressing_mode_cycles
+    }
+
+    // Instructions run per raw opcode byte while `profiling` is set.
+    pub fn opcode_histogram(&self) -> &[u64; 256] {
+        &self.opcode_histogram
+    }
+
+    pub fn reset_histogram(&mut self) {
+        self.opcode_histogram = [0; 256];
+    }
+
+    // Set even with count_cycles off, so it can be used without cycle counts.
+    pub fn last_page_crossed(&self) -> bool {
+        self.last_page_crossed
+    }
+
+    pub fn last_cycle_breakdown(&self) -> CycleBreakdown {
+        let penalties = self.last_branch_penalty + self.last_page_cross_penalty;
+        CycleBreakdown {
+            base: self.last_pc_cycles - penalties,
+            branch_penalty: self.last_branch_penalty,
+            page_cross_penalty: self.last_page_cross_penalty,
+        }
+    }
+
+    pub fn total_cycles(&self) -> u64 {
+        self.total_cycles
+    }
+
+    pub fn total_instructions(&self) -> u64 {
+        self.total_instructions
+    }
+
+    pub fn reset_counters(&mut self) {
+        self.total_cycles = 0;
+        self.total_instructions = 0;
+        self.num_instructions_executed_debug = 0;
+    }
+
+    // Full stack address, including the page when `extended_stack` is set.
+    pub fn get_extended_stack_pointer(&self) -> u16 {
+        u16::from_le_bytes([self.registers.stack_pointer, self.stack_page()])
+    }
+
+    // First stack wrap seen since the last call, if stack_guard is set.
+    pub fn take_stack_fault(&mut self) -> Option<StackFault> {
+        self.stack_fault.take()
+    }
+
+    // Byte `depth` entries into the stack without pulling it; depth 0 is the
+    // last byte pushed. Wraps within the stack page like pull_stack does.
+    pub fn peek_stack<T:BusPeek + ?Sized>(&self, bus:&T, depth:u8) -> u8 {
+        let addr = if self.extended_stack {
+            self.get_extended_stack_pointer().wrapping_add(1 + depth as u16)
+        } else {
+            u16::from_le_bytes([self.registers.stack_pointer.wrapping_add(1).wrapping_add(depth), 0x01])
+        };
+        bus.peek_byte_at(addr)
+    }
+
+    // Pushes an interrupt frame as an IRQ would (PC high, PC low, then status
+    // with B clear), so a following RTI resumes at `return_addr` with `status`.
+    pub fn push_frame<T:BusInterface + ?Sized>(&mut self, bus:&mut T, return_addr:u16, status:u8) {
+        let pc_bytes = return_addr.to_le_bytes();
+        self.push_stack(bus, pc_bytes[1]);
+        self.push_stack(bus, pc_bytes[0]);
+        self.push_stack(bus, ProcessorStatus::from(status).as_stack_byte(false));
+    }
+
+}
+
+
+// Fills in the nine addressing modes of an accumulator ALU op, the last being
+// the 65C02's (zp). Operand fetch is shared so the modes can't drift apart;
+// only `op` differs.
+macro_rules! alu_op {
+    ($t:ident, [$imm:ident, $z:ident, $zx:ident, $abs:ident, $absx:ident, $absy:ident, $indx:ident, $indy:ident, $indz:ident],
+     |$cpu:ident, $val:ident| $op:expr) => {
+        $t[Opcode::$imm as usize] = |$cpu, _, pipe_byte1, _| {
+            let $val = pipe_byte1;
+            $op;
+        };
+        $t[Opcode::$z as usize] = |$cpu, bus, pipe_byte1, _| {
+            let $val = $cpu.read_byte(bus, pipe_byte1 as u16);
+            $op;
+        };
+        $t[Opcode::$zx as usize] = |$cpu, bus, pipe_byte1, _| {
+            let addr = zero_page_indexed(pipe_byte1, $cpu.registers.x);
+            let $val = $cpu.read_byte(bus, addr);
+            $op;
+        };
+        $t[Opcode::$abs as usize] = |$cpu, bus, pipe_byte1, pipe_byte2| {
+            let $val = $cpu.read_byte(bus, u16::from_le_bytes([pipe_byte1, pipe_byte2]));
+            $op;
+        };
+        $t[Opcode::$absx as usize] = |$cpu, bus, pipe_byte1, pipe_byte2| {
+            let addr = $cpu.abs_indexed_read_addr(bus, pipe_byte1, pipe_byte2, $cpu.registers.x);
+            let $val = $cpu.read_byte(bus, addr);
+            $op;
+        };
+        $t[Opcode::$absy as usize] = |$cpu, bus, pipe_byte1, pipe_byte2| {
+            let addr = $cpu.abs_indexed_read_addr(bus, pipe_byte1, pipe_byte2, $cpu.registers.y);
+            let $val = $cpu.read_byte(bus, addr);
+            $op;
+        };
+        $t[Opcode::$indx as usize] = |$cpu, bus, pipe_byte1, _| {
+            let addr = $cpu.indirect_x_addr(bus, pipe_byte1, $cpu.registers.x);
+            let $val = $cpu.read_byte(bus, addr);
+            $op;
+        };
+        $t[Opcode::$indy as usize] = |$cpu, bus, pipe_byte1, _| {
+            let addr = $cpu.indirect_y_read_addr(bus, pipe_byte1, $cpu.registers.y);
+            let $val = $cpu.read_byte(bus, addr);
+            $op;
+        };
+        $t[Opcode::$indz as usize] = |$cpu, bus, pipe_byte1, _| {
+            let addr = $cpu.zero_page_pointer(bus, pipe_byte1);
+            let $val = $cpu.read_byte(bus, addr);
+            $op;
+        };
+    };
+}
+
+// 65C02 RMBn/SMBn clear/set bit n of a zero page byte; BBRn/BBSn read it and
+// branch if bit n is clear/set.
+macro_rules! zp_bit_op {
+    ($t:ident, $bit:literal, $rmb:ident, $smb:ident, $bbr:ident, $bbs:ident) => {
+        $t[Opcode::$rmb as usize] = |cpu, bus, pipe_byte1, _| {
+            cpu.read_modify_write(bus, pipe_byte1 as u16, |_, val| val & !(1 << $bit));
+        };
+        $t[Opcode::$smb as usize] = |cpu, bus, pipe_byte1, _| {
+            cpu.read_modify_write(bus, pipe_byte1 as u16, |_, val| val | (1 << $bit));
+        };
+        $t[Opcode::$bbr as usize] = |cpu, bus, pipe_byte1, pipe_byte2| {
+            if cpu.read_byte(bus, pipe_byte1 as u16) & (1 << $bit) == 0 {
+                cpu.branch_by_offset(pipe_byte2);
+            }
+        };
+        $t[Opcode::$bbs as usize] = |cpu, bus, pipe_byte1, pipe_byte2| {
+            if cpu.read_byte(bus, pipe_byte1 as u16) & (1 << $bit) != 0 {
+                cpu.branch_by_offset(pipe_byte2);
+            }
+        };
+    };
 }
 
+type OpcodeHandler<T> = fn(&mut Nmos6502, &mut T, u8, u8);
+
+// Dispatch table for tick, indexed by Opcode discriminant. That is the opcode
+// byte for NMOS opcodes and $100 above it for 65C02-only ones, hence 512
+// entries: two 256-byte opcode spaces. Anything the variant doesn't have
+// decodes to UNREC. A slot left without a handler halts with UnknownOpcode
+// rather than quietly running as a NOP.
+struct OpcodeTable<T:?Sized>(PhantomData<T>);
+
+impl<T:BusInterface + ?Sized> OpcodeTable<T> {
+    const HANDLERS: [OpcodeHandler<T>; 512] = {
+        let mut t:[OpcodeHandler<T>; 512] = [|cpu, _, _, _| {
+            cpu.halt(HaltReason::UnknownOpcode(cpu.last_opcode_byte, cpu.last_pc_debug));
+        }; 512];
+        alu_op!(t, [ADCimm, ADCz, ADCzX, ADCabs, ADCabsX, ADCabsY, ADCindX, ADCindY, ADCindZ], |cpu, val| {
+            cpu.add_with_carry(val)
+        });
+        alu_op!(t, [ANDimm, ANDz, ANDzX, ANDabs, ANDabsX, ANDabsY, ANDindX, ANDindY, ANDindZ], |cpu, val| {
+            cpu.registers.accumulator &= val;
+            cpu.processor_status.update_zero_neg_flags(cpu.registers.accumulator)
+        });
+        alu_op!(t, [CMPimm, CMPz, CMPzX, CMPabs, CMPabsx, CMPabsy, CMPindX, CMPindY, CMPindZ], |cpu, val| {
+            cpu.processor_status.update_flags_with_compare(cpu.registers.accumulator, val)
+        });
+        alu_op!(t, [EORimm, EORz, EORzX, EORabs, EORabsX, EORabsY, EORindX, EORindY, EORindZ], |cpu, val| {
+            cpu.registers.accumulator ^= val;
+            cpu.processor_status.update_zero_neg_flags(cpu.registers.accumulator)
+        });
+        alu_op!(t, [LDAimm, LDAz, LDAzX, LDAabs, LDAabsX, LDAabsY, LDAindX, LDAindY, LDAindZ], |cpu, val| {
+            cpu.registers.accumulator = val;
+            cpu.processor_status.update_zero_neg_flags(cpu.registers.accumulator)
+        });
+        alu_op!(t, [ORAimm, ORAz, ORAzX, ORAabs, ORAabsX, ORAabsY, ORAindX, ORAindY, ORAindZ], |cpu, val| {
+            cpu.registers.accumulator |= val;
+            cpu.processor_status.update_zero_neg_flags(cpu.registers.accumulator)
+        });
+        alu_op!(t, [SBCimm, SBCz, SBCzX, SBCabs, SBCabsX, SBCabsY, SBCindX, SBCindY, SBCindZ], |cpu, val| {
+            cpu.subtract_with_carry(val)
+        });
+        t[Opcode::ASLabs as usize] = |cpu, bus, pipe_byte1, pipe_byte2| {
+            let addr = u16::from_le_bytes([pipe_byte1, pipe_byte2]);
+            cpu.read_modify_write(bus, addr, Nmos6502::arithmetic_shift_left);
+        };
+        t[Opcode::ASLabsX as usize] = |cpu, bus, pipe_byte1, pipe_byte2| {
+            let addr = cpu.abs_indexed_write_addr(bus, pipe_byte1, pipe_byte2, cpu.registers.x);
+            cpu.read_modify_write(bus, addr, Nmos6502::arithmetic_shift_left);
+        };
+        t[Opcode::ASLacc as usize] = |cpu, _, _, _| {
+            cpu.registers.accumulator = cpu.arithmetic_shift_left(cpu.registers.accumulator);
+        };
+        t[Opcode::ASLz as usize] = |cpu, bus, pipe_byte1, _| {
+            let addr = pipe_byte1 as u16;
+            cpu.read_modify_write(bus, addr, Nmos6502::arithmetic_shift_left);
+        };
+        t[Opcode::ASLzX as usize] = |cpu, bus, pipe_byte1, _| {
+            let addr = zero_page_indexed(pipe_byte1, cpu.registers.x);
+            cpu.read_modify_write(bus, addr, Nmos6502::arithmetic_shift_left);
+        };
+        t[Opcode::BITabs as usize] = |cpu, bus, pipe_byte1, pipe_byte2| {
+            let addr = u16::from_le_bytes([pipe_byte1, pipe_byte2]);
+            let val = cpu.read_byte(bus, addr);
+            cpu.bit_test(val);
+        };
+        t[Opcode::BITz as usize] = |cpu, bus, pipe_byte1, _| {
+            let addr = pipe_byte1 as u16;
+            let val = cpu.read_byte(bus, addr);
+            cpu.bit_test(val);
+        };
+        t[Opcode::DECabs as usize] = |cpu, bus, pipe_byte1, pipe_byte2| {
+            let addr = u16::from_le_bytes([pipe_byte1, pipe_byte2]);
+            cpu.read_modify_write(bus, addr, Nmos6502::decrement);
+        };
+        t[Opcode::DECabsX as usize] = |cpu, bus, pipe_byte1, pipe_byte2| {
+            let addr = cpu.abs_indexed_write_addr(bus, pipe_byte1, pipe_byte2, cpu.registers.x);
+            cpu.read_modify_write(bus, addr, Nmos6502::decrement);
+        };
+        t[Opcode::DECz as usize] = |cpu, bus, pipe_byte1, _| {
+            let addr = pipe_byte1 as u16;
+            cpu.read_modify_write(bus, addr, Nmos6502::decrement);
+        };
+        t[Opcode::DECzX as usize] = |cpu, bus, pipe_byte1, _| {
+            let addr = zero_page_indexed(pipe_byte1, cpu.registers.x);
+            cpu.read_modify_write(bus, addr, Nmos6502::decrement);
+        };
+        t[Opcode::JMP as usize] = |cpu, _, pipe_byte1, pipe_byte2| {
+            cpu.registers.program_counter = u16::from_le_bytes([pipe_byte1, pipe_byte2]);
+        };
+        t[Opcode::JMPi as usize] = |cpu, bus, pipe_byte1, pipe_byte2| {
+            let indirect_jmp_addr =u16::from_le_bytes([pipe_byte1, pipe_byte2]);
+            cpu.registers.program_counter = cpu.jmp_indirect_target(bus, indirect_jmp_addr);
+        };
+        t[Opcode::JSR as usize] = |cpu, bus, pipe_byte1, pipe_byte2| {
+            let jmp_addr = u16::from_le_bytes([pipe_byte1, pipe_byte2]);
+            let pc_rtn_addr_bytes = cpu.registers.program_counter.wrapping_sub(1).to_le_bytes();
+            cpu.push_stack(bus, pc_rtn_addr_bytes[1]);
+            cpu.push_stack(bus, pc_rtn_addr_bytes[0]);
+            cpu.registers.program_counter = jmp_addr;
+        };
+        t[Opcode::LDXabs as usize] = |cpu, bus, pipe_byte1, pipe_byte2| {
+            let get_addr = u16::from_le_bytes([pipe_byte1, pipe_byte2]);
+            cpu.registers.x = cpu.read_byte(bus, get_addr);
+            cpu.processor_status.update_zero_neg_flags(cpu.registers.x);
+        };
+        t[Opcode::LDXabsY as usize] = |cpu, bus, pipe_byte1, pipe_byte2| {
+            let get_addr = cpu.abs_indexed_read_addr(bus, pipe_byte1, pipe_byte2, cpu.registers.y);
+            cpu.registers.x = cpu.read_byte(bus, get_addr);
+            cpu.processor_status.update_zero_neg_flags(cpu.registers.x);
+        };
+        t[Opcode::LDXimm as usize] = |cpu, _, pipe_byte1, _| {
+            cpu.registers.x = pipe_byte1;
+            cpu.processor_status.update_zero_neg_flags(cpu.registers.x);
+        };
+        t[Opcode::LDXz as usize] = |cpu, bus, pipe_byte1, _| {
+            let addr = pipe_byte1 as u16;
+            cpu.registers.x = cpu.read_byte(bus, addr);
+            cpu.processor_status.update_zero_neg_flags(cpu.registers.x);
+        };
+        t[Opcode::LDXzy as usize] = |cpu, bus, pipe_byte1, _| {
+            let addr = zero_page_indexed(pipe_byte1, cpu.registers.y);
+            cpu.registers.x = cpu.read_byte(bus, addr);
+            cpu.processor_status.update_zero_neg_flags(cpu.registers.x);
+        };
+        t[Opcode::LDYabs as usize] = |cpu, bus, pipe_byte1, pipe_byte2| {
+            let get_addr = u16::from_le_bytes([pipe_byte1, pipe_byte2]);
+            cpu.registers.y = cpu.read_byte(bus, get_addr);
+            cpu.processor_status.update_zero_neg_flags(cpu.registers.y);
+        };
+        t[Opcode::LDYabsX as usize] = |cpu, bus, pipe_byte1, pipe_byte2| {
+            let get_addr = cpu.abs_indexed_read_addr(bus, pipe_byte1, pipe_byte2, cpu.registers.x);
+            cpu.registers.y = cpu.read_byte(bus, get_addr);
+            cpu.processor_status.update_zero_neg_flags(cpu.registers.y);
+        };
+        t[Opcode::LDYimm as usize] = |cpu, _, pipe_byte1, _| {
+            cpu.registers.y = pipe_byte1;
+            cpu.processor_status.update_zero_neg_flags(cpu.registers.y);
+        };
+        t[Opcode::LDYz as usize] = |cpu, bus, pipe_byte1, _| {
+            let addr = pipe_byte1 as u16;
+            cpu.registers.y = cpu.read_byte(bus, addr);
+            cpu.processor_status.update_zero_neg_flags(cpu.registers.y);
+        };
+        t[Opcode::LDYzx as usize] = |cpu, bus, pipe_byte1, _| {
+            let addr = zero_page_indexed(pipe_byte1, cpu.registers.x);
+            cpu.registers.y = cpu.read_byte(bus, addr);
+            cpu.processor_status.update_zero_neg_flags(cpu.registers.y);
+        };
+        t[Opcode::LSRabs as usize] = |cpu, bus, pipe_byte1, pipe_byte2| {
+            let addr = u16::from_le_bytes([pipe_byte1, pipe_byte2]);
+            cpu.read_modify_write(bus, addr, Nmos6502::logical_shift_right);
+        };
+        t[Opcode::LSRabsX as usize] = |cpu, bus, pipe_byte1, pipe_byte2| {
+            let addr = cpu.abs_indexed_write_addr(bus, pipe_byte1, pipe_byte2, cpu.registers.x);
+            cpu.read_modify_write(bus, addr, Nmos6502::logical_shift_right);
+        };
+        t[Opcode::LSRacc as usize] = |cpu, _, _, _| {
+            cpu.registers.accumulator = cpu.logical_shift_right(cpu.registers.accumulator);
+        };
+        t[Opcode::LSRz as usize] = |cpu, bus, pipe_byte1, _| {
+            let addr = pipe_byte1 as u16;
+            cpu.read_modify_write(bus, addr, Nmos6502::logical_shift_right);
+        };
+        t[Opcode::LSRzX as usize] = |cpu, bus, pipe_byte1, _| {
+            let addr = zero_page_indexed(pipe_byte1, cpu.registers.x);
+            cpu.read_modify_write(bus, addr, Nmos6502::logical_shift_right);
+        };
+        t[Opcode::ROLabs as usize] = |cpu, bus, pipe_byte1, pipe_byte2| {
+            let addr = u16::from_le_bytes([pipe_byte1, pipe_byte2]);
+            cpu.read_modify_write(bus, addr, Nmos6502::rotate_left);
+        };
+        t[Opcode::ROLabsX as usize] = |cpu, bus, pipe_byte1, pipe_byte2| {
+            let addr = cpu.abs_indexed_write_addr(bus, pipe_byte1, pipe_byte2, cpu.registers.x);
+            cpu.read_modify_write(bus, addr, Nmos6502::rotate_left);
+        };
+        t[Opcode::ROLacc as usize] = |cpu, _, _, _| {
+            cpu.registers.accumulator = cpu.rotate_left(cpu.registers.accumulator);
+        };
+        t[Opcode::ROLz as usize] = |cpu, bus, pipe_byte1, _| {
+            let addr = pipe_byte1 as u16;
+            cpu.read_modify_write(bus, addr, Nmos6502::rotate_left);
+        };
+        t[Opcode::ROLzX as usize] = |cpu, bus, pipe_byte1, _| {
+            let addr = zero_page_indexed(pipe_byte1, cpu.registers.x);
+            cpu.read_modify_write(bus, addr, Nmos6502::rotate_left);
+        };
+        t[Opcode::RORabs as usize] = |cpu, bus, pipe_byte1, pipe_byte2| {
+            let addr = u16::from_le_bytes([pipe_byte1, pipe_byte2]);
+            cpu.read_modify_write(bus, addr, Nmos6502::rotate_right);
+        };
+        t[Opcode::RORabsX as usize] = |cpu, bus, pipe_byte1, pipe_byte2| {
+            let addr = cpu.abs_indexed_write_addr(bus, pipe_byte1, pipe_byte2, cpu.registers.x);
+            cpu.read_modify_write(bus, addr, Nmos6502::rotate_right);
+        };
+        t[Opcode::RORacc as usize] = |cpu, _, _, _| {
+            cpu.registers.accumulator = cpu.rotate_right(cpu.registers.accumulator);
+        };
+        t[Opcode::RORz as usize] = |cpu, bus, pipe_byte1, _| {
+            let addr = pipe_byte1 as u16;
+            cpu.read_modify_write(bus, addr, Nmos6502::rotate_right);
+        };
+        t[Opcode::RORzX as usize] = |cpu, bus, pipe_byte1, _| {
+            let addr = zero_page_indexed(pipe_byte1, cpu.registers.x);
+            cpu.read_modify_write(bus, addr, Nmos6502::rotate_right);
+        };
+        t[Opcode::RTI as usize] = |cpu, bus, _, _| {
+            // unlike PLP, the restored I flag is polled straight away: no
+            // polled_interrupt_disable, as on hardware
+            let status = cpu.pull_stack(bus);
+            cpu.processor_status = ProcessorStatus::from_stack(status);
+
+            let ret_addr_lo = cpu.pull_stack(bus);
+            let ret_addr_hi =  cpu.pull_stack(bus);
+            let ret_addr = u16::from_le_bytes([ret_addr_lo, ret_addr_hi]);
+
+            cpu.registers.program_counter = ret_addr;
+        };
+        t[Opcode::RTS as usize] = |cpu, bus, _, _| {
+            let ret_addr_lo = cpu.pull_stack(bus);
+            let ret_addr_hi =  cpu.pull_stack(bus);
+            let ret_addr = u16::from_le_bytes([ret_addr_lo, ret_addr_hi]).wrapping_add(1);
+            cpu.registers.program_counter = ret_addr;
+        };
+        t[Opcode::STA as usize] = |cpu, bus, pipe_byte1, pipe_byte2| {
+            let set_addr = u16::from_le_bytes([pipe_byte1, pipe_byte2]);
+            cpu.write_byte(bus, set_addr, cpu.registers.accumulator);
+        };
+        t[Opcode::STAz as usize] = |cpu, bus, pipe_byte1, _| {
+            let set_addr = pipe_byte1 as u16;
+            cpu.write_byte(bus, set_addr, cpu.registers.accumulator);
+        };
+        t[Opcode::STAzX as usize] = |cpu, bus, pipe_byte1, _| {
+            let set_addr = zero_page_indexed(pipe_byte1, cpu.registers.x);
+            cpu.write_byte(bus, set_addr, cpu.registers.accumulator);
+        };
+        t[Opcode::STAabsX as usize] = |cpu, bus, pipe_byte1, pipe_byte2| { // store accumulator absolute + relative X
+            let set_addr = cpu.abs_indexed_write_addr(bus, pipe_byte1, pipe_byte2, cpu.registers.x);
+            cpu.write_byte(bus, set_addr, cpu.registers.accumulator);
+        };
+        t[Opcode::STAay as usize] = |cpu, bus, pipe_byte1, pipe_byte2| {
+            let set_addr = cpu.abs_indexed_write_addr(bus, pipe_byte1, pipe_byte2, cpu.registers.y);
+            cpu.write_byte(bus, set_addr, cpu.registers.accumulator);
+        };
+        t[Opcode::STAindX as usize] = |cpu, bus, pipe_byte1, _| {
+            let addr =  cpu.indirect_x_addr(bus,pipe_byte1, cpu.registers.x);
+
+            cpu.write_byte(bus, addr, cpu.registers.accumulator);
+        };
+        t[Opcode::STAindY as usize] = |cpu, bus, pipe_byte1, _| {
+            let addr = cpu.indirect_y_write_addr(bus, pipe_byte1, cpu.registers.y);
+            cpu.write_byte(bus, addr, cpu.registers.accumulator);
+        };
+        t[Opcode::STAindZ as usize] = |cpu, bus, pipe_byte1, _| {
+            let addr = cpu.zero_page_pointer(bus, pipe_byte1);
+            cpu.write_byte(bus, addr, cpu.registers.accumulator);
+        };
+        t[Opcode::STX as usize] = |cpu, bus, pipe_byte1, pipe_byte2| {
+            let set_addr = u16::from_le_bytes([pipe_byte1, pipe_byte2]);
+            cpu.write_byte(bus, set_addr, cpu.registers.x);
+        };
+        t[Opcode::STXz as usize] = |cpu, bus, pipe_byte1, _| {
+            let set_addr = pipe_byte1 as u16;
+            cpu.write_byte(bus, set_addr, cpu.registers.x);
+        };
+        t[Opcode::STXzY as usize] = |cpu, bus, pipe_byte1, _| {
+            let set_addr = zero_page_indexed(pipe_byte1, cpu.registers.y);
+            cpu.write_byte(bus, set_addr, cpu.registers.x);
+        };
+        t[Opcode::STY as usize] = |cpu, bus, pipe_byte1, pipe_byte2| {
+            let set_addr = u16::from_le_bytes([pipe_byte1, pipe_byte2]);
+            cpu.write_byte(bus, set_addr, cpu.registers.y);
+        };
+        t[Opcode::STYz as usize] = |cpu, bus, pipe_byte1, _| {
+            let set_addr = pipe_byte1 as u16;
+            cpu.write_byte(bus, set_addr, cpu.registers.y);
+        };
+        t[Opcode::STYzX as usize] = |cpu, bus, pipe_byte1, _| {
+            let set_addr = zero_page_indexed(pipe_byte1, cpu.registers.x);
+            cpu.write_byte(bus, set_addr, cpu.registers.y);
+        };
+        t[Opcode::TXS as usize] = |cpu, _, _, _| { // transfer X to SP
+            cpu.registers.stack_pointer = cpu.registers.x;
+        };
+        t[Opcode::TSX as usize] = |cpu, _, _, _| { // transfer SP to X
+            cpu.registers.x = cpu.registers.stack_pointer;
+            cpu.processor_status.update_zero_neg_flags(cpu.registers.x);
+        };
+        t[Opcode::TAX as usize] = |cpu, _, _, _| {
+            cpu.registers.x = cpu.registers.accumulator;
+            cpu.processor_status.update_zero_neg_flags(cpu.registers.x);
+        };
+        t[Opcode::TXA as usize] = |cpu, _, _, _| {
+            cpu.registers.accumulator = cpu.registers.x;
+            cpu.processor_status.update_zero_neg_flags(cpu.registers.accumulator);
+        };
+        t[Opcode::TAY as usize] = |cpu, _, _, _| {
+            cpu.registers.y = cpu.registers.accumulator;
+            cpu.processor_status.update_zero_neg_flags(cpu.registers.y);
+        };
+        t[Opcode::TYA as usize] = |cpu, _, _, _| {
+            cpu.registers.accumulator = cpu.registers.y;
+            cpu.processor_status.update_zero_neg_flags(cpu.registers.accumulator);
+        };
+        t[Opcode::PHP as usize] = |cpu, bus, _, _| {
+            // PHP pushes B set, like BRK
+            cpu.push_stack(bus, cpu.processor_status.as_stack_byte(true));
+        };
+        t[Opcode::PHA as usize] = |cpu, bus, _, _| {
+            cpu.push_stack(bus, cpu.registers.accumulator);
+        };
+        t[Opcode::PLA as usize] = |cpu, bus, _, _| {
+            cpu.registers.accumulator = cpu.pull_stack(bus);
+            cpu.processor_status.update_zero_neg_flags(cpu.registers.accumulator);
+        };
+        t[Opcode::PLP as usize] = |cpu, bus, _, _| {
+            // errata: bflag0 and 1 can not be pulled with PLP
+            // these two bits do not physically exist on the real processor, and always report as 1
+            let status = cpu.pull_stack(bus);
+            cpu.polled_interrupt_disable = Some(cpu.processor_status.interrupt_disable());
+            cpu.processor_status = ProcessorStatus::from_stack(status);
+        };
+        t[Opcode::CLC as usize] = |cpu, _, _, _| {
+            cpu.processor_status.clr_carry();
+        };
+        t[Opcode::SEC as usize] = |cpu, _, _, _| {
+            cpu.processor_status.set_carry();
+        };
+        t[Opcode::CLD as usize] = |cpu, _, _, _| {
+            cpu.processor_status.clr_decimal();
+        };
+        t[Opcode::SED as usize] = |cpu, _, _, _| {
+            cpu.processor_status.set_decimal();
+        };
+        t[Opcode::CLI as usize] = |cpu, _, _, _| {
+            cpu.polled_interrupt_disable = Some(cpu.processor_status.interrupt_disable());
+            cpu.processor_status.clr_interrupt_disable();
+        };
+        t[Opcode::SEI as usize] = |cpu, _, _, _| {
+            cpu.polled_interrupt_disable = Some(cpu.processor_status.interrupt_disable());
+            cpu.processor_status.set_interrupt_disable();
+        };
+        t[Opcode::CLV as usize] = |cpu, _, _, _| {
+            cpu.processor_status.clr_overflow();
+        };
+        t[Opcode::INX as usize] = |cpu, _, _, _| {
+            cpu.registers.x = cpu.registers.x.wrapping_add(1);
+            cpu.processor_status.update_zero_neg_flags(cpu.registers.x);
+        };
+        t[Opcode::DEX as usize] = |cpu, _, _, _| {
+            cpu.registers.x = cpu.registers.x.wrapping_sub(1);
+            cpu.processor_status.update_zero_neg_flags(cpu.registers.x);
+        };
+        t[Opcode::INY as usize] = |cpu, _, _, _| {
+            cpu.registers.y = cpu.registers.y.wrapping_add(1);
+            cpu.processor_status.update_zero_neg_flags(cpu.registers.y);
+        };
+        t[Opcode::INCabs as usize] = |cpu, bus, pipe_byte1, pipe_byte2| {
+            let addr = u16::from_le_bytes([pipe_byte1, pipe_byte2]);
+            cpu.read_modify_write(bus, addr, Nmos6502::increment);
+        };
+        t[Opcode::INCabsx as usize] = |cpu, bus, pipe_byte1, pipe_byte2| {
+            let addr = cpu.abs_indexed_write_addr(bus, pipe_byte1, pipe_byte2, cpu.registers.x);
+            cpu.read_modify_write(bus, addr, Nmos6502::increment);
+        };
+        t[Opcode::INCz as usize] = |cpu, bus, pipe_byte1, _| {
+            let addr = pipe_byte1 as u16;
+            cpu.read_modify_write(bus, addr, Nmos6502::increment);
+        };
+        t[Opcode::INCzx as usize] = |cpu, bus, pipe_byte1, _| { // note: we are supposed to wrap within pages
+            let addr = zero_page_indexed(pipe_byte1, cpu.registers.x);
+            cpu.read_modify_write(bus, addr, Nmos6502::increment);
+        };
+        t[Opcode::DEY as usize] = |cpu, _, _, _| {
+            cpu.registers.y = cpu.registers.y.wrapping_sub(1);
+            cpu.processor_status.update_zero_neg_flags(cpu.registers.y);
+        };
+        t[Opcode::BCC as usize] = |cpu, _, pipe_byte1, _| {
+            if !cpu.processor_status.carry() {
+                cpu.branch_by_offset(pipe_byte1);
+            }
+        };
+        t[Opcode::BCS as usize] = |cpu, _, pipe_byte1, _| {
+            if cpu.processor_status.carry() {
+                cpu.branch_by_offset(pipe_byte1);
+            }
+        };
+        t[Opcode::BEQ as usize] = |cpu, _, pipe_byte1, _| {
+            if cpu.processor_status.zero() {
+                cpu.branch_by_offset(pipe_byte1);
+            }
+        };
+        t[Opcode::BNE as usize] = |cpu, _, pipe_byte1, _| {
+            if !cpu.processor_status.zero() {
+                cpu.branch_by_offset(pipe_byte1);
+            }
+        };
+        t[Opcode::BPL as usize] = |cpu, _, pipe_byte1, _| {
+            if !cpu.processor_status.negative() {
+                cpu.branch_by_offset(pipe_byte1);
+            }
+        };
+        t[Opcode::BMI as usize] = |cpu, _, pipe_byte1, _| {
+            if cpu.processor_status.negative() {
+                cpu.branch_by_offset(pipe_byte1);
+            }
+        };
+        t[Opcode::BVC as usize] = |cpu, _, pipe_byte1, _| {
+            if !cpu.processor_status.overflow() {
+                cpu.branch_by_offset(pipe_byte1);
+            }
+        };
+        t[Opcode::BVS as usize] = |cpu, _, pipe_byte1, _| {
+            if cpu.processor_status.overflow() {
+                cpu.branch_by_offset(pipe_byte1);
+            }
+        };
+        t[Opcode::CPX as usize] = |cpu, _, pipe_byte1, _| {
+            cpu.processor_status.update_flags_with_compare(cpu.registers.x,pipe_byte1);
+        };
+        t[Opcode::CPXz as usize] = |cpu, bus, pipe_byte1, _| {
+            let get_addr = pipe_byte1 as u16;
+            let cmp_val = cpu.read_byte(bus, get_addr);
+            cpu.processor_status.update_flags_with_compare(cpu.registers.x,cmp_val);
+        };
+        t[Opcode::CPXabs as usize] = |cpu, bus, pipe_byte1, pipe_byte2| {
+            let get_addr = u16::from_le_bytes([pipe_byte1, pipe_byte2]);
+            let cmp_val = cpu.read_byte(bus, get_addr);
+            cpu.processor_status.update_flags_with_compare(cpu.registers.x, cmp_val);
+        };
+        t[Opcode::CPY as usize] = |cpu, _, pipe_byte1, _| {
+            cpu.processor_status.update_flags_with_compare(cpu.registers.y,pipe_byte1);
+        };
+        t[Opcode::CPYz as usize] = |cpu, bus, pipe_byte1, _| {
+            let get_addr = pipe_byte1 as u16;
+            let val = cpu.read_byte(bus, get_addr);
+            cpu.processor_status.update_flags_with_compare(cpu.registers.y,val);
+        };
+        t[Opcode::CPYabs as usize] = |cpu, bus, pipe_byte1, pipe_byte2| {
+            let get_addr = u16::from_le_bytes([pipe_byte1, pipe_byte2]);
+            let val = cpu.read_byte(bus, get_addr);
+            cpu.processor_status.update_flags_with_compare(cpu.registers.y, val);
+        };
+        t[Opcode::BRK as usize] = |cpu, bus, _, _| {
+            cpu.push_stack_interrupt(InterruptKind::Brk, bus);
+            cpu.break_flag_ext_debug = true;
+        };
+        t[Opcode::NOP as usize] = |_, _, _, _| ();
+        // Begin "illegal" opcodes
+        t[Opcode::NOPi0 as usize] = |_, _, _, _| ();
+        t[Opcode::NOPim as usize] = |_, _, _, _| ();
+        t[Opcode::NOPim2 as usize] = |_, _, _, _| ();
+        t[Opcode::NOPim3 as usize] = |_, _, _, _| ();
+        t[Opcode::NOPim4 as usize] = |_, _, _, _| ();
+        t[Opcode::NOPim5 as usize] = |_, _, _, _| ();
+        t[Opcode::NOPim6 as usize] = |_, _, _, _| ();
+        t[Opcode::NOPi2 as usize] = |_, _, _, _| ();
+        t[Opcode::NOPi3 as usize] = |_, _, _, _| ();
+        t[Opcode::NOPi4 as usize] = |_, _, _, _| ();
+        t[Opcode::NOPi5 as usize] = |_, _, _, _| ();
+        // The NOPs with a memory operand still perform the read, so they cost
+        // the same cycles and bus traffic as LDA in that mode.
+        let nop_z: OpcodeHandler<T> = |cpu, bus, pipe_byte1, _| {
+            cpu.read_byte(bus, pipe_byte1 as u16);
+        };
+        t[Opcode::NOPz0 as usize] = nop_z;
+        t[Opcode::NOPz1 as usize] = nop_z;
+        t[Opcode::NOPz2 as usize] = nop_z;
+        let nop_zx: OpcodeHandler<T> = |cpu, bus, pipe_byte1, _| {
+            let get_addr = zero_page_indexed(pipe_byte1, cpu.registers.x);
+            cpu.read_byte(bus, get_addr);
+        };
+        t[Opcode::NOPzX0 as usize] = nop_zx;
+        t[Opcode::NOPzX1 as usize] = nop_zx;
+        t[Opcode::NOPzX2 as usize] = nop_zx;
+        t[Opcode::NOPzX3 as usize] = nop_zx;
+        t[Opcode::NOPzX4 as usize] = nop_zx;
+        t[Opcode::NOPzX5 as usize] = nop_zx;
+        t[Opcode::NOPabs as usize] = |cpu, bus, pipe_byte1, pipe_byte2| {
+            cpu.read_byte(bus, u16::from_le_bytes([pipe_byte1, pipe_byte2]));
+        };
+        let nop_abs_x: OpcodeHandler<T> = |cpu, bus, pipe_byte1, pipe_byte2| {
+            let get_addr = cpu.abs_indexed_read_addr(bus, pipe_byte1, pipe_byte2, cpu.registers.x);
+            cpu.read_byte(bus, get_addr);
+        };
+        t[Opcode::NOPabsX0 as usize] = nop_abs_x;
+        t[Opcode::NOPabsX1 as usize] = nop_abs_x;
+        t[Opcode::NOPabsX2 as usize] = nop_abs_x;
+        t[Opcode::NOPabsX3 as usize] = nop_abs_x;
+        t[Opcode::NOPabsX4 as usize] = nop_abs_x;
+        t[Opcode::NOPabsX5 as usize] = nop_abs_x;
+        // 65C02 only
+        t[Opcode::JMPiX as usize] = |cpu, bus, pipe_byte1, pipe_byte2| {
+            let pointer = u16::from_le_bytes([pipe_byte1, pipe_byte2]).wrapping_add(cpu.registers.x as u16);
+            cpu.registers.program_counter = cpu.read_word(bus, pointer);
+        };
+        t[Opcode::TSBz as usize] = |cpu, bus, pipe_byte1, _| {
+            cpu.read_modify_write(bus, pipe_byte1 as u16, Nmos6502::test_and_set_bits);
+        };
+        t[Opcode::TSBabs as usize] = |cpu, bus, pipe_byte1, pipe_byte2| {
+            let addr = u16::from_le_bytes([pipe_byte1, pipe_byte2]);
+            cpu.read_modify_write(bus, addr, Nmos6502::test_and_set_bits);
+        };
+        t[Opcode::TRBz as usize] = |cpu, bus, pipe_byte1, _| {
+            cpu.read_modify_write(bus, pipe_byte1 as u16, Nmos6502::test_and_reset_bits);
+        };
+        t[Opcode::TRBabs as usize] = |cpu, bus, pipe_byte1, pipe_byte2| {
+            let addr = u16::from_le_bytes([pipe_byte1, pipe_byte2]);
+            cpu.read_modify_write(bus, addr, Nmos6502::test_and_reset_bits);
+        };
+        zp_bit_op!(t, 0, RMB0, SMB0, BBR0, BBS0);
+        zp_bit_op!(t, 1, RMB1, SMB1, BBR1, BBS1);
+        zp_bit_op!(t, 2, RMB2, SMB2, BBR2, BBS2);
+        zp_bit_op!(t, 3, RMB3, SMB3, BBR3, BBS3);
+        zp_bit_op!(t, 4, RMB4, SMB4, BBR4, BBS4);
+        zp_bit_op!(t, 5, RMB5, SMB5, BBR5, BBS5);
+        zp_bit_op!(t, 6, RMB6, SMB6, BBR6, BBS6);
+        zp_bit_op!(t, 7, RMB7, SMB7, BBR7, BBS7);
+        t[Opcode::UNREC as usize] = |cpu, _, _, _| {
+            cpu.uncaught_opcode_debug = Some(cpu.last_opcode_byte);
+            if cpu.strict_illegal {
+                cpu.halt(HaltReason::UnknownOpcode(cpu.last_opcode_byte, cpu.last_pc_debug));
+            }
+        }; // "Illegal" implied NOP (here for debug)
+        t
+    };
+}
 
 pub(crate) struct Registers {
     program_counter: u16,
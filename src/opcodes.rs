@@ -1,7 +1,12 @@
 use num_enum::{FromPrimitive};
+use crate::variant::Variant;
 
-#[derive(Copy,Clone,Debug,FromPrimitive)]
-#[repr(u8)]
+// Discriminants are the opcode bytes, with $100 added for the opcodes only the
+// 65C02 has so they never collide with an NMOS one. From<u8> is the NMOS
+// decode, as it always was: bytes with no NMOS opcode, $FF included, are UNREC.
+// From<u16> looks up discriminants. Use Opcode::decode for other variants.
+#[derive(Copy,Clone,Debug,PartialEq,Eq,FromPrimitive)]
+#[repr(u16)]
 pub enum Opcode {
     ADCabs = 0x6D,
     ADCabsX = 0x7D,
@@ -181,13 +186,516 @@ pub enum Opcode {
     NOPabsX3 = 0x7C,
     NOPabsX4 = 0xDC,
     NOPabsX5 = 0xFC,
+    // 65C02 zero page bit opcodes
+    RMB0 = 0x107,
+    RMB1 = 0x117,
+    RMB2 = 0x127,
+    RMB3 = 0x137,
+    RMB4 = 0x147,
+    RMB5 = 0x157,
+    RMB6 = 0x167,
+    RMB7 = 0x177,
+    SMB0 = 0x187,
+    SMB1 = 0x197,
+    SMB2 = 0x1A7,
+    SMB3 = 0x1B7,
+    SMB4 = 0x1C7,
+    SMB5 = 0x1D7,
+    SMB6 = 0x1E7,
+    SMB7 = 0x1F7,
+    BBR0 = 0x10F,
+    BBR1 = 0x11F,
+    BBR2 = 0x12F,
+    BBR3 = 0x13F,
+    BBR4 = 0x14F,
+    BBR5 = 0x15F,
+    BBR6 = 0x16F,
+    BBR7 = 0x17F,
+    BBS0 = 0x18F,
+    BBS1 = 0x19F,
+    BBS2 = 0x1AF,
+    BBS3 = 0x1BF,
+    BBS4 = 0x1CF,
+    BBS5 = 0x1DF,
+    BBS6 = 0x1EF,
+    BBS7 = 0x1FF,
+    // 65C02 (zp) addressing
+    ORAindZ = 0x112,
+    ANDindZ = 0x132,
+    EORindZ = 0x152,
+    ADCindZ = 0x172,
+    STAindZ = 0x192,
+    LDAindZ = 0x1B2,
+    CMPindZ = 0x1D2,
+    SBCindZ = 0x1F2,
+    // 65C02 opcodes on bytes the NMOS part uses for illegal NOPs
+    TSBz = 0x104,
+    TSBabs = 0x10C,
+    TRBz = 0x114,
+    TRBabs = 0x11C,
+    JMPiX = 0x17C,
     // ILLEGAL OPERATIONS / OPERATION NOT FOUND
     #[num_enum(default)]
     UNREC = 0xFF
 }
 
+impl From<u8> for Opcode {
+    fn from(byte:u8) -> Opcode {
+        Opcode::from(byte as u16)
+    }
+}
+
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum AddressingMode {
+    Implied,
+    Accumulator,
+    Immediate,
+    ZeroPage,
+    ZeroPageX,
+    ZeroPageY,
+    Absolute,
+    AbsoluteX,
+    AbsoluteY,
+    Indirect,
+    IndirectX,
+    IndirectY,
+    Relative,
+    // 65C02 BBR/BBS: a zero page address, then a branch offset.
+    ZeroPageRelative,
+    // 65C02 (zp): IndirectY without the Y.
+    ZeroPageIndirect,
+    // 65C02 JMP (abs,X)
+    AbsoluteIndirectX,
+}
+
+impl AddressingMode {
+    // For arrays indexed by `mode as usize`: one past the last mode, so a new
+    // mode added at the end of the enum must be named here.
+    pub const COUNT: usize = AddressingMode::AbsoluteIndirectX as usize + 1;
+}
+
 impl Opcode {
 
+    // `byte` as `variant` executes it; opcodes the variant lacks are UNREC.
+    pub fn decode(byte:u8, variant:Variant) -> Opcode {
+        let cmos = Opcode::from(0x100 | byte as u16);
+        let op = if cmos != Opcode::UNREC && cmos.exists_on(variant) { cmos } else { Opcode::from(byte) };
+        if op.exists_on(variant) { op } else { Opcode::UNREC }
+    }
+
+    // Reverse of the byte decode, for assembling. Only documented NMOS opcodes are returned.
+    pub fn encode(mnemonic:&str, mode:AddressingMode) -> Option<Opcode> {
+        Opcode::all().find(|op| {
+            !op.is_illegal() && op.exists_on(Variant::Nmos6502) && op.addressing_mode() == mode && op.mnemonic().eq_ignore_ascii_case(mnemonic)
+        })
+    }
+
+    // Every opcode of every variant, documented and illegal: the NMOS ones in
+    // byte order, then the 65C02 additions in byte order. Never UNREC. Filter
+    // with exists_on for one variant's set.
+    pub fn all() -> impl Iterator<Item = Opcode> {
+        let nmos = (0..=0xFFu8).map(|byte| Opcode::decode(byte, Variant::Nmos6502));
+        let cmos_only = (0..=0xFFu8).map(|byte| Opcode::decode(byte, Variant::Cmos65C02)).filter(Opcode::is_cmos);
+        nmos.chain(cmos_only).filter(|op| *op != Opcode::UNREC)
+    }
+
+    pub fn as_byte(&self) -> u8 {
+        *self as u16 as u8
+    }
+
+    // Whether `variant` has this opcode. The 65C02 gave most of the NMOS
+    // illegal NOP bytes new opcodes; only the ones still NOPs there are kept.
+    pub fn exists_on(&self, variant:Variant) -> bool {
+        match variant {
+            Variant::Nmos6502 | Variant::Nes2A03 | Variant::Mos6510 => !self.is_cmos(),
+            Variant::Cmos65C02 => !self.is_illegal() || matches!(*self,
+                Opcode::NOPi2 | Opcode::NOPi4 | Opcode::NOPi5 | Opcode::NOPz1 |
+                Opcode::NOPzX2 | Opcode::NOPzX4 | Opcode::NOPzX5 | Opcode::NOPabsX4 |
+                Opcode::NOPabsX5),
+        }
+    }
+
+    // Opcodes added by the 65C02.
+    pub fn is_cmos(&self) -> bool {
+        matches!(*self,
+            Opcode::RMB0 | Opcode::RMB1 | Opcode::RMB2 | Opcode::RMB3 |
+            Opcode::RMB4 | Opcode::RMB5 | Opcode::RMB6 | Opcode::RMB7 |
+            Opcode::SMB0 | Opcode::SMB1 | Opcode::SMB2 | Opcode::SMB3 |
+            Opcode::SMB4 | Opcode::SMB5 | Opcode::SMB6 | Opcode::SMB7 |
+            Opcode::BBR0 | Opcode::BBR1 | Opcode::BBR2 | Opcode::BBR3 |
+            Opcode::BBR4 | Opcode::BBR5 | Opcode::BBR6 | Opcode::BBR7 |
+            Opcode::BBS0 | Opcode::BBS1 | Opcode::BBS2 | Opcode::BBS3 |
+            Opcode::BBS4 | Opcode::BBS5 | Opcode::BBS6 | Opcode::BBS7 |
+            Opcode::TSBz | Opcode::TSBabs | Opcode::TRBz | Opcode::TRBabs |
+            Opcode::ORAindZ | Opcode::ANDindZ | Opcode::EORindZ | Opcode::ADCindZ |
+            Opcode::STAindZ | Opcode::LDAindZ | Opcode::CMPindZ | Opcode::SBCindZ |
+            Opcode::JMPiX)
+    }
+
+    // Undocumented NMOS opcodes, including bytes this core doesn't recognize.
+    pub fn is_illegal(&self) -> bool {
+        matches!(*self,
+            Opcode::NOPim | Opcode::NOPi0 | Opcode::NOPim2 | Opcode::NOPim3 |
+            Opcode::NOPim4 | Opcode::NOPim5 | Opcode::NOPim6 | Opcode::NOPi2 |
+            Opcode::NOPi3 | Opcode::NOPi4 | Opcode::NOPi5 | Opcode::NOPz0 |
+            Opcode::NOPz1 | Opcode::NOPz2 | Opcode::NOPzX0 | Opcode::NOPzX1 |
+            Opcode::NOPzX2 | Opcode::NOPzX3 | Opcode::NOPzX4 | Opcode::NOPzX5 |
+            Opcode::NOPabs | Opcode::NOPabsX0 | Opcode::NOPabsX1 | Opcode::NOPabsX2 |
+            Opcode::NOPabsX3 | Opcode::NOPabsX4 | Opcode::NOPabsX5 | Opcode::UNREC)
+    }
+
+    pub fn mnemonic(&self) -> &'static str {
+        match *self {
+            Opcode::ADCabs | Opcode::ADCabsX | Opcode::ADCabsY | Opcode::ADCimm |
+            Opcode::ADCindX | Opcode::ADCindY | Opcode::ADCz | Opcode::ADCzX |
+            Opcode::ADCindZ => "ADC",
+            Opcode::ANDabs | Opcode::ANDabsX | Opcode::ANDabsY | Opcode::ANDimm |
+            Opcode::ANDindX | Opcode::ANDindY | Opcode::ANDz | Opcode::ANDzX |
+            Opcode::ANDindZ => "AND",
+            Opcode::ASLabs | Opcode::ASLabsX | Opcode::ASLacc | Opcode::ASLz |
+            Opcode::ASLzX => "ASL",
+            Opcode::BCC => "BCC",
+            Opcode::BCS => "BCS",
+            Opcode::BEQ => "BEQ",
+            Opcode::BITabs | Opcode::BITz => "BIT",
+            Opcode::BMI => "BMI",
+            Opcode::BNE => "BNE",
+            Opcode::BPL => "BPL",
+            Opcode::BRK => "BRK",
+            Opcode::BVC => "BVC",
+            Opcode::BVS => "BVS",
+            Opcode::CLC => "CLC",
+            Opcode::CLD => "CLD",
+            Opcode::CLI => "CLI",
+            Opcode::CLV => "CLV",
+            Opcode::CMPabs | Opcode::CMPabsx | Opcode::CMPabsy | Opcode::CMPimm |
+            Opcode::CMPindX | Opcode::CMPindY | Opcode::CMPz | Opcode::CMPzX |
+            Opcode::CMPindZ => "CMP",
+            Opcode::CPX | Opcode::CPXabs | Opcode::CPXz => "CPX",
+            Opcode::CPY | Opcode::CPYabs | Opcode::CPYz => "CPY",
+            Opcode::DECabs | Opcode::DECabsX | Opcode::DECz | Opcode::DECzX => "DEC",
+            Opcode::DEX => "DEX",
+            Opcode::DEY => "DEY",
+            Opcode::EORabs | Opcode::EORabsX | Opcode::EORabsY | Opcode::EORimm |
+            Opcode::EORindX | Opcode::EORindY | Opcode::EORz | Opcode::EORzX |
+            Opcode::EORindZ => "EOR",
+            Opcode::INCabs | Opcode::INCabsx | Opcode::INCz | Opcode::INCzx => "INC",
+            Opcode::INX => "INX",
+            Opcode::INY => "INY",
+            Opcode::JMP | Opcode::JMPi | Opcode::JMPiX => "JMP",
+            Opcode::JSR => "JSR",
+            Opcode::LDAabs | Opcode::LDAabsX | Opcode::LDAabsY | Opcode::LDAimm |
+            Opcode::LDAindX | Opcode::LDAindY | Opcode::LDAz | Opcode::LDAzX |
+            Opcode::LDAindZ => "LDA",
+            Opcode::LDXabs | Opcode::LDXabsY | Opcode::LDXimm | Opcode::LDXz |
+            Opcode::LDXzy => "LDX",
+            Opcode::LDYabs | Opcode::LDYabsX | Opcode::LDYimm | Opcode::LDYz |
+            Opcode::LDYzx => "LDY",
+            Opcode::LSRabs | Opcode::LSRabsX | Opcode::LSRacc | Opcode::LSRz |
+            Opcode::LSRzX => "LSR",
+            Opcode::NOP | Opcode::NOPim | Opcode::NOPi0 | Opcode::NOPim2 |
+            Opcode::NOPim3 | Opcode::NOPim4 | Opcode::NOPim5 | Opcode::NOPim6 |
+            Opcode::NOPi2 | Opcode::NOPi3 | Opcode::NOPi4 | Opcode::NOPi5 |
+            Opcode::NOPz0 | Opcode::NOPz1 | Opcode::NOPz2 | Opcode::NOPzX0 |
+            Opcode::NOPzX1 | Opcode::NOPzX2 | Opcode::NOPzX3 | Opcode::NOPzX4 |
+            Opcode::NOPzX5 | Opcode::NOPabs | Opcode::NOPabsX0 | Opcode::NOPabsX1 |
+            Opcode::NOPabsX2 | Opcode::NOPabsX3 | Opcode::NOPabsX4 | Opcode::NOPabsX5 => "NOP",
+            Opcode::ORAabs | Opcode::ORAabsX | Opcode::ORAabsY | Opcode::ORAimm |
+            Opcode::ORAindX | Opcode::ORAindY | Opcode::ORAz | Opcode::ORAzX |
+            Opcode::ORAindZ => "ORA",
+            Opcode::PHA => "PHA",
+            Opcode::PHP => "PHP",
+            Opcode::PLA => "PLA",
+            Opcode::PLP => "PLP",
+            Opcode::ROLabs | Opcode::ROLabsX | Opcode::ROLacc | Opcode::ROLz |
+            Opcode::ROLzX => "ROL",
+            Opcode::RORabs | Opcode::RORabsX | Opcode::RORacc | Opcode::RORz |
+            Opcode::RORzX => "ROR",
+            Opcode::RTI => "RTI",
+            Opcode::RTS => "RTS",
+            Opcode::SBCabs | Opcode::SBCabsX | Opcode::SBCabsY | Opcode::SBCindX |
+            Opcode::SBCindY | Opcode::SBCimm | Opcode::SBCz | Opcode::SBCzX |
+            Opcode::SBCindZ => "SBC",
+            Opcode::SEC => "SEC",
+            Opcode::SED => "SED",
+            Opcode::SEI => "SEI",
+            Opcode::STA | Opcode::STAabsX | Opcode::STAay | Opcode::STAindX |
+            Opcode::STAindY | Opcode::STAz | Opcode::STAzX | Opcode::STAindZ => "STA",
+            Opcode::STX | Opcode::STXz | Opcode::STXzY => "STX",
+            Opcode::STY | Opcode::STYz | Opcode::STYzX => "STY",
+            Opcode::TAX => "TAX",
+            Opcode::TAY => "TAY",
+            Opcode::TSX => "TSX",
+            Opcode::TXA => "TXA",
+            Opcode::TXS => "TXS",
+            Opcode::TYA => "TYA",
+            Opcode::RMB0 => "RMB0",
+            Opcode::RMB1 => "RMB1",
+            Opcode::RMB2 => "RMB2",
+            Opcode::RMB3 => "RMB3",
+            Opcode::RMB4 => "RMB4",
+            Opcode::RMB5 => "RMB5",
+            Opcode::RMB6 => "RMB6",
+            Opcode::RMB7 => "RMB7",
+            Opcode::SMB0 => "SMB0",
+            Opcode::SMB1 => "SMB1",
+            Opcode::SMB2 => "SMB2",
+            Opcode::SMB3 => "SMB3",
+            Opcode::SMB4 => "SMB4",
+            Opcode::SMB5 => "SMB5",
+            Opcode::SMB6 => "SMB6",
+            Opcode::SMB7 => "SMB7",
+            Opcode::BBR0 => "BBR0",
+            Opcode::BBR1 => "BBR1",
+            Opcode::BBR2 => "BBR2",
+            Opcode::BBR3 => "BBR3",
+            Opcode::BBR4 => "BBR4",
+            Opcode::BBR5 => "BBR5",
+            Opcode::BBR6 => "BBR6",
+            Opcode::BBR7 => "BBR7",
+            Opcode::BBS0 => "BBS0",
+            Opcode::BBS1 => "BBS1",
+            Opcode::BBS2 => "BBS2",
+            Opcode::BBS3 => "BBS3",
+            Opcode::BBS4 => "BBS4",
+            Opcode::BBS5 => "BBS5",
+            Opcode::BBS6 => "BBS6",
+            Opcode::BBS7 => "BBS7",
+            Opcode::TSBz | Opcode::TSBabs => "TSB",
+            Opcode::TRBz | Opcode::TRBabs => "TRB",
+            Opcode::UNREC => "???",
+        }
+    }
+
+    pub fn addressing_mode(&self) -> AddressingMode {
+        match *self {
+            Opcode::ADCabs => AddressingMode::Absolute,
+            Opcode::ADCabsX => AddressingMode::AbsoluteX,
+            Opcode::ADCabsY => AddressingMode::AbsoluteY,
+            Opcode::ADCimm => AddressingMode::Immediate,
+            Opcode::ADCindX => AddressingMode::IndirectX,
+            Opcode::ADCindY => AddressingMode::IndirectY,
+            Opcode::ADCz => AddressingMode::ZeroPage,
+            Opcode::ADCzX => AddressingMode::ZeroPageX,
+            Opcode::ANDabs => AddressingMode::Absolute,
+            Opcode::ANDabsX => AddressingMode::AbsoluteX,
+            Opcode::ANDabsY => AddressingMode::AbsoluteY,
+            Opcode::ANDimm => AddressingMode::Immediate,
+            Opcode::ANDindX => AddressingMode::IndirectX,
+            Opcode::ANDindY => AddressingMode::IndirectY,
+            Opcode::ANDz => AddressingMode::ZeroPage,
+            Opcode::ANDzX => AddressingMode::ZeroPageX,
+            Opcode::ASLabs => AddressingMode::Absolute,
+            Opcode::ASLabsX => AddressingMode::AbsoluteX,
+            Opcode::ASLacc => AddressingMode::Accumulator,
+            Opcode::ASLz => AddressingMode::ZeroPage,
+            Opcode::ASLzX => AddressingMode::ZeroPageX,
+            Opcode::BCC => AddressingMode::Relative,
+            Opcode::BCS => AddressingMode::Relative,
+            Opcode::BEQ => AddressingMode::Relative,
+            Opcode::BITabs => AddressingMode::Absolute,
+            Opcode::BITz => AddressingMode::ZeroPage,
+            Opcode::BMI => AddressingMode::Relative,
+            Opcode::BNE => AddressingMode::Relative,
+            Opcode::BPL => AddressingMode::Relative,
+            Opcode::BRK => AddressingMode::Implied,
+            Opcode::BVC => AddressingMode::Relative,
+            Opcode::BVS => AddressingMode::Relative,
+            Opcode::CLC => AddressingMode::Implied,
+            Opcode::CLD => AddressingMode::Implied,
+            Opcode::CLI => AddressingMode::Implied,
+            Opcode::CLV => AddressingMode::Implied,
+            Opcode::CMPabs => AddressingMode::Absolute,
+            Opcode::CMPabsx => AddressingMode::AbsoluteX,
+            Opcode::CMPabsy => AddressingMode::AbsoluteY,
+            Opcode::CMPimm => AddressingMode::Immediate,
+            Opcode::CMPindX => AddressingMode::IndirectX,
+            Opcode::CMPindY => AddressingMode::IndirectY,
+            Opcode::CMPz => AddressingMode::ZeroPage,
+            Opcode::CMPzX => AddressingMode::ZeroPageX,
+            Opcode::CPX => AddressingMode::Immediate,
+            Opcode::CPXabs => AddressingMode::Absolute,
+            Opcode::CPXz => AddressingMode::ZeroPage,
+            Opcode::CPY => AddressingMode::Immediate,
+            Opcode::CPYabs => AddressingMode::Absolute,
+            Opcode::CPYz => AddressingMode::ZeroPage,
+            Opcode::DECabs => AddressingMode::Absolute,
+            Opcode::DECabsX => AddressingMode::AbsoluteX,
+            Opcode::DECz => AddressingMode::ZeroPage,
+            Opcode::DECzX => AddressingMode::ZeroPageX,
+            Opcode::DEX => AddressingMode::Implied,
+            Opcode::DEY => AddressingMode::Implied,
+            Opcode::EORabs => AddressingMode::Absolute,
+            Opcode::EORabsX => AddressingMode::AbsoluteX,
+            Opcode::EORabsY => AddressingMode::AbsoluteY,
+            Opcode::EORimm => AddressingMode::Immediate,
+            Opcode::EORindX => AddressingMode::IndirectX,
+            Opcode::EORindY => AddressingMode::IndirectY,
+            Opcode::EORz => AddressingMode::ZeroPage,
+            Opcode::EORzX => AddressingMode::ZeroPageX,
+            Opcode::INCabs => AddressingMode::Absolute,
+            Opcode::INCabsx => AddressingMode::AbsoluteX,
+            Opcode::INCz => AddressingMode::ZeroPage,
+            Opcode::INCzx => AddressingMode::ZeroPageX,
+            Opcode::INX => AddressingMode::Implied,
+            Opcode::INY => AddressingMode::Implied,
+            Opcode::JMP => AddressingMode::Absolute,
+            Opcode::JMPi => AddressingMode::Indirect,
+            Opcode::JSR => AddressingMode::Absolute,
+            Opcode::LDAabs => AddressingMode::Absolute,
+            Opcode::LDAabsX => AddressingMode::AbsoluteX,
+            Opcode::LDAabsY => AddressingMode::AbsoluteY,
+            Opcode::LDAimm => AddressingMode::Immediate,
+            Opcode::LDAindX => AddressingMode::IndirectX,
+            Opcode::LDAindY => AddressingMode::IndirectY,
+            Opcode::LDAz => AddressingMode::ZeroPage,
+            Opcode::LDAzX => AddressingMode::ZeroPageX,
+            Opcode::LDXabs => AddressingMode::Absolute,
+            Opcode::LDXabsY => AddressingMode::AbsoluteY,
+            Opcode::LDXimm => AddressingMode::Immediate,
+            Opcode::LDXz => AddressingMode::ZeroPage,
+            Opcode::LDXzy => AddressingMode::ZeroPageY,
+            Opcode::LDYabs => AddressingMode::Absolute,
+            Opcode::LDYabsX => AddressingMode::AbsoluteX,
+            Opcode::LDYimm => AddressingMode::Immediate,
+            Opcode::LDYz => AddressingMode::ZeroPage,
+            Opcode::LDYzx => AddressingMode::ZeroPageX,
+            Opcode::LSRabs => AddressingMode::Absolute,
+            Opcode::LSRabsX => AddressingMode::AbsoluteX,
+            Opcode::LSRacc => AddressingMode::Accumulator,
+            Opcode::LSRz => AddressingMode::ZeroPage,
+            Opcode::LSRzX => AddressingMode::ZeroPageX,
+            Opcode::NOP => AddressingMode::Implied,
+            Opcode::ORAabs => AddressingMode::Absolute,
+            Opcode::ORAabsX => AddressingMode::AbsoluteX,
+            Opcode::ORAabsY => AddressingMode::AbsoluteY,
+            Opcode::ORAimm => AddressingMode::Immediate,
+            Opcode::ORAindX => AddressingMode::IndirectX,
+            Opcode::ORAindY => AddressingMode::IndirectY,
+            Opcode::ORAz => AddressingMode::ZeroPage,
+            Opcode::ORAzX => AddressingMode::ZeroPageX,
+            Opcode::PHA => AddressingMode::Implied,
+            Opcode::PHP => AddressingMode::Implied,
+            Opcode::PLA => AddressingMode::Implied,
+            Opcode::PLP => AddressingMode::Implied,
+            Opcode::ROLabs => AddressingMode::Absolute,
+            Opcode::ROLabsX => AddressingMode::AbsoluteX,
+            Opcode::ROLacc => AddressingMode::Accumulator,
+            Opcode::ROLz => AddressingMode::ZeroPage,
+            Opcode::ROLzX => AddressingMode::ZeroPageX,
+            Opcode::RORabs => AddressingMode::Absolute,
+            Opcode::RORabsX => AddressingMode::AbsoluteX,
+            Opcode::RORacc => AddressingMode::Accumulator,
+            Opcode::RORz => AddressingMode::ZeroPage,
+            Opcode::RORzX => AddressingMode::ZeroPageX,
+            Opcode::RTI => AddressingMode::Implied,
+            Opcode::RTS => AddressingMode::Implied,
+            Opcode::SBCabs => AddressingMode::Absolute,
+            Opcode::SBCabsX => AddressingMode::AbsoluteX,
+            Opcode::SBCabsY => AddressingMode::AbsoluteY,
+            Opcode::SBCindX => AddressingMode::IndirectX,
+            Opcode::SBCindY => AddressingMode::IndirectY,
+            Opcode::SBCimm => AddressingMode::Immediate,
+            Opcode::SBCz => AddressingMode::ZeroPage,
+            Opcode::SBCzX => AddressingMode::ZeroPageX,
+            Opcode::SEC => AddressingMode::Implied,
+            Opcode::SED => AddressingMode::Implied,
+            Opcode::SEI => AddressingMode::Implied,
+            Opcode::STA => AddressingMode::Absolute,
+            Opcode::STAabsX => AddressingMode::AbsoluteX,
+            Opcode::STAay => AddressingMode::AbsoluteY,
+            Opcode::STAindX => AddressingMode::IndirectX,
+            Opcode::STAindY => AddressingMode::IndirectY,
+            Opcode::STAz => AddressingMode::ZeroPage,
+            Opcode::STAzX => AddressingMode::ZeroPageX,
+            Opcode::STX => AddressingMode::Absolute,
+            Opcode::STXz => AddressingMode::ZeroPage,
+            Opcode::STXzY => AddressingMode::ZeroPageY,
+            Opcode::STY => AddressingMode::Absolute,
+            Opcode::STYz => AddressingMode::ZeroPage,
+            Opcode::STYzX => AddressingMode::ZeroPageX,
+            Opcode::TAX => AddressingMode::Implied,
+            Opcode::TAY => AddressingMode::Implied,
+            Opcode::TSX => AddressingMode::Implied,
+            Opcode::TXA => AddressingMode::Implied,
+            Opcode::TXS => AddressingMode::Implied,
+            Opcode::TYA => AddressingMode::Implied,
+            Opcode::NOPim => AddressingMode::Implied,
+            Opcode::NOPi0 => AddressingMode::Immediate,
+            Opcode::NOPim2 => AddressingMode::Implied,
+            Opcode::NOPim3 => AddressingMode::Implied,
+            Opcode::NOPim4 => AddressingMode::Implied,
+            Opcode::NOPim5 => AddressingMode::Implied,
+            Opcode::NOPim6 => AddressingMode::Implied,
+            Opcode::NOPi2 => AddressingMode::Immediate,
+            Opcode::NOPi3 => AddressingMode::Immediate,
+            Opcode::NOPi4 => AddressingMode::Immediate,
+            Opcode::NOPi5 => AddressingMode::Immediate,
+            Opcode::NOPz0 => AddressingMode::ZeroPage,
+            Opcode::NOPz1 => AddressingMode::ZeroPage,
+            Opcode::NOPz2 => AddressingMode::ZeroPage,
+            Opcode::NOPzX0 => AddressingMode::ZeroPageX,
+            Opcode::NOPzX1 => AddressingMode::ZeroPageX,
+            Opcode::NOPzX2 => AddressingMode::ZeroPageX,
+            Opcode::NOPzX3 => AddressingMode::ZeroPageX,
+            Opcode::NOPzX4 => AddressingMode::ZeroPageX,
+            Opcode::NOPzX5 => AddressingMode::ZeroPageX,
+            Opcode::NOPabs => AddressingMode::Absolute,
+            Opcode::NOPabsX0 => AddressingMode::AbsoluteX,
+            Opcode::NOPabsX1 => AddressingMode::AbsoluteX,
+            Opcode::NOPabsX2 => AddressingMode::AbsoluteX,
+            Opcode::NOPabsX3 => AddressingMode::AbsoluteX,
+            Opcode::NOPabsX4 => AddressingMode::AbsoluteX,
+            Opcode::NOPabsX5 => AddressingMode::AbsoluteX,
+            Opcode::RMB0 => AddressingMode::ZeroPage,
+            Opcode::RMB1 => AddressingMode::ZeroPage,
+            Opcode::RMB2 => AddressingMode::ZeroPage,
+            Opcode::RMB3 => AddressingMode::ZeroPage,
+            Opcode::RMB4 => AddressingMode::ZeroPage,
+            Opcode::RMB5 => AddressingMode::ZeroPage,
+            Opcode::RMB6 => AddressingMode::ZeroPage,
+            Opcode::RMB7 => AddressingMode::ZeroPage,
+            Opcode::SMB0 => AddressingMode::ZeroPage,
+            Opcode::SMB1 => AddressingMode::ZeroPage,
+            Opcode::SMB2 => AddressingMode::ZeroPage,
+            Opcode::SMB3 => AddressingMode::ZeroPage,
+            Opcode::SMB4 => AddressingMode::ZeroPage,
+            Opcode::SMB5 => AddressingMode::ZeroPage,
+            Opcode::SMB6 => AddressingMode::ZeroPage,
+            Opcode::SMB7 => AddressingMode::ZeroPage,
+            Opcode::BBR0 => AddressingMode::ZeroPageRelative,
+            Opcode::BBR1 => AddressingMode::ZeroPageRelative,
+            Opcode::BBR2 => AddressingMode::ZeroPageRelative,
+            Opcode::BBR3 => AddressingMode::ZeroPageRelative,
+            Opcode::BBR4 => AddressingMode::ZeroPageRelative,
+            Opcode::BBR5 => AddressingMode::ZeroPageRelative,
+            Opcode::BBR6 => AddressingMode::ZeroPageRelative,
+            Opcode::BBR7 => AddressingMode::ZeroPageRelative,
+            Opcode::BBS0 => AddressingMode::ZeroPageRelative,
+            Opcode::BBS1 => AddressingMode::ZeroPageRelative,
+            Opcode::BBS2 => AddressingMode::ZeroPageRelative,
+            Opcode::BBS3 => AddressingMode::ZeroPageRelative,
+            Opcode::BBS4 => AddressingMode::ZeroPageRelative,
+            Opcode::BBS5 => AddressingMode::ZeroPageRelative,
+            Opcode::BBS6 => AddressingMode::ZeroPageRelative,
+            Opcode::BBS7 => AddressingMode::ZeroPageRelative,
+            Opcode::TSBz => AddressingMode::ZeroPage,
+            Opcode::TSBabs => AddressingMode::Absolute,
+            Opcode::TRBz => AddressingMode::ZeroPage,
+            Opcode::TRBabs => AddressingMode::Absolute,
+            Opcode::ORAindZ => AddressingMode::ZeroPageIndirect,
+            Opcode::ANDindZ => AddressingMode::ZeroPageIndirect,
+            Opcode::EORindZ => AddressingMode::ZeroPageIndirect,
+            Opcode::ADCindZ => AddressingMode::ZeroPageIndirect,
+            Opcode::STAindZ => AddressingMode::ZeroPageIndirect,
+            Opcode::LDAindZ => AddressingMode::ZeroPageIndirect,
+            Opcode::CMPindZ => AddressingMode::ZeroPageIndirect,
+            Opcode::SBCindZ => AddressingMode::ZeroPageIndirect,
+            Opcode::JMPiX => AddressingMode::AbsoluteIndirectX,
+            Opcode::UNREC => AddressingMode::Implied,
+        }
+    }
+
     pub(crate) fn cycle_inc(&self) -> u8 {
         match *self {
             Opcode::ADCabs => 4,
@@ -323,8 +831,8 @@ impl Opcode {
             Opcode::SED => 2,
             Opcode::SEI => 2,
             Opcode::STA => 4,
-            Opcode::STAabsX => 4,
-            Opcode::STAay => 4,
+            Opcode::STAabsX => 5,
+            Opcode::STAay => 5,
             Opcode::STAindX => 6,
             Opcode::STAindY => 6,
             Opcode::STAz => 3,
@@ -364,10 +872,55 @@ impl Opcode {
             Opcode::NOPabs => 4,
             Opcode::NOPabsX0 => 4,
             Opcode::NOPabsX1 => 4,
-            Opcode::NOPabsX2 => 2,
+            Opcode::NOPabsX2 => 4,
             Opcode::NOPabsX3 => 4,
             Opcode::NOPabsX4 => 4,
             Opcode::NOPabsX5 => 4,
+            Opcode::RMB0 => 5,
+            Opcode::RMB1 => 5,
+            Opcode::RMB2 => 5,
+            Opcode::RMB3 => 5,
+            Opcode::RMB4 => 5,
+            Opcode::RMB5 => 5,
+            Opcode::RMB6 => 5,
+            Opcode::RMB7 => 5,
+            Opcode::SMB0 => 5,
+            Opcode::SMB1 => 5,
+            Opcode::SMB2 => 5,
+            Opcode::SMB3 => 5,
+            Opcode::SMB4 => 5,
+            Opcode::SMB5 => 5,
+            Opcode::SMB6 => 5,
+            Opcode::SMB7 => 5,
+            Opcode::BBR0 => 5,
+            Opcode::BBR1 => 5,
+            Opcode::BBR2 => 5,
+            Opcode::BBR3 => 5,
+            Opcode::BBR4 => 5,
+            Opcode::BBR5 => 5,
+            Opcode::BBR6 => 5,
+            Opcode::BBR7 => 5,
+            Opcode::BBS0 => 5,
+            Opcode::BBS1 => 5,
+            Opcode::BBS2 => 5,
+            Opcode::BBS3 => 5,
+            Opcode::BBS4 => 5,
+            Opcode::BBS5 => 5,
+            Opcode::BBS6 => 5,
+            Opcode::BBS7 => 5,
+            Opcode::TSBz => 5,
+            Opcode::TSBabs => 6,
+            Opcode::TRBz => 5,
+            Opcode::TRBabs => 6,
+            Opcode::ORAindZ => 5,
+            Opcode::ANDindZ => 5,
+            Opcode::EORindZ => 5,
+            Opcode::ADCindZ => 5,
+            Opcode::STAindZ => 5,
+            Opcode::LDAindZ => 5,
+            Opcode::CMPindZ => 5,
+            Opcode::SBCindZ => 5,
+            Opcode::JMPiX => 6,
             Opcode::UNREC => 2,
         }
     }
@@ -403,7 +956,7 @@ impl Opcode {
             Opcode::BMI => 2,
             Opcode::BNE => 2,
             Opcode::BPL => 2,
-            Opcode::BRK => 2,
+            Opcode::BRK => 2, // opcode + signature byte, so RTI resumes after the signature
             Opcode::BVC => 2,
             Opcode::BVS => 2,
             Opcode::CLC => 1,
@@ -552,6 +1105,51 @@ impl Opcode {
             Opcode::NOPabsX3 => 3,
             Opcode::NOPabsX4 => 3,
             Opcode::NOPabsX5 => 3,
+            Opcode::RMB0 => 2,
+            Opcode::RMB1 => 2,
+            Opcode::RMB2 => 2,
+            Opcode::RMB3 => 2,
+            Opcode::RMB4 => 2,
+            Opcode::RMB5 => 2,
+            Opcode::RMB6 => 2,
+            Opcode::RMB7 => 2,
+            Opcode::SMB0 => 2,
+            Opcode::SMB1 => 2,
+            Opcode::SMB2 => 2,
+            Opcode::SMB3 => 2,
+            Opcode::SMB4 => 2,
+            Opcode::SMB5 => 2,
+            Opcode::SMB6 => 2,
+            Opcode::SMB7 => 2,
+            Opcode::BBR0 => 3,
+            Opcode::BBR1 => 3,
+            Opcode::BBR2 => 3,
+            Opcode::BBR3 => 3,
+            Opcode::BBR4 => 3,
+            Opcode::BBR5 => 3,
+            Opcode::BBR6 => 3,
+            Opcode::BBR7 => 3,
+            Opcode::BBS0 => 3,
+            Opcode::BBS1 => 3,
+            Opcode::BBS2 => 3,
+            Opcode::BBS3 => 3,
+            Opcode::BBS4 => 3,
+            Opcode::BBS5 => 3,
+            Opcode::BBS6 => 3,
+            Opcode::BBS7 => 3,
+            Opcode::TSBz => 2,
+            Opcode::TSBabs => 3,
+            Opcode::TRBz => 2,
+            Opcode::TRBabs => 3,
+            Opcode::ORAindZ => 2,
+            Opcode::ANDindZ => 2,
+            Opcode::EORindZ => 2,
+            Opcode::ADCindZ => 2,
+            Opcode::STAindZ => 2,
+            Opcode::LDAindZ => 2,
+            Opcode::CMPindZ => 2,
+            Opcode::SBCindZ => 2,
+            Opcode::JMPiX => 3,
             Opcode::UNREC => 1,
         }
     }
@@ -1,7 +1,23 @@
-pub(crate) struct ProcessorStatus {
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub struct ProcessorStatus {
     byte: u8
 }
 
+#[derive(Copy,Clone,Debug,Default,PartialEq,Eq)]
+pub struct StatusFlags {
+    pub carry: bool,
+    pub zero: bool,
+    pub interrupt_disable: bool,
+    pub decimal: bool,
+    pub overflow: bool,
+    pub negative: bool,
+}
+
+// Bit 5 (unused) and bit 4 (B) have no storage on the chip. The CPU's status
+// always holds them set; they only differ in the copy pushed by IRQ/NMI. A
+// ProcessorStatus built from a stacked byte keeps them as pushed, for break_bit.
+pub const STATUS_UNUSED_BITS: u8 = 0b0011_0000;
+
 impl From<u8> for ProcessorStatus {
     fn from(b:u8) -> Self {
         ProcessorStatus { byte: b }
@@ -10,6 +26,42 @@ impl From<u8> for ProcessorStatus {
 
 // (N)eg | o(V)erflow | b0 | b1 | (D)ecimal | (I)nterrupt | (Z)ero | (C)arry 
 impl ProcessorStatus {
+    // Bits 4 and 5 are set, as they always are in the CPU's own status.
+    pub fn with_flags(carry:bool, zero:bool, interrupt_disable:bool, decimal:bool, overflow:bool, negative:bool) -> Self {
+        let mut status = ProcessorStatus { byte: STATUS_UNUSED_BITS };
+        if carry { status.set_carry(); }
+        if zero { status.set_zero(); }
+        if interrupt_disable { status.set_interrupt_disable(); }
+        if decimal { status.set_decimal(); }
+        if overflow { status.set_overflow(); }
+        if negative { status.set_negative(); }
+        status
+    }
+
+    // The status PLP/RTI load from a pulled byte. Bits 4 and 5 can't be
+    // pulled, so they're set whatever the stacked byte held.
+    pub fn from_stack(byte:u8) -> Self {
+        ProcessorStatus { byte: byte | STATUS_UNUSED_BITS }
+    }
+
+    // The byte pushed to the stack: bit 5 always set, B set for BRK/PHP and
+    // clear for IRQ/NMI.
+    pub fn as_stack_byte(&self, from_brk:bool) -> u8 {
+        let b_flag = if from_brk { 0b0001_0000 } else { 0 };
+        (self.byte & !STATUS_UNUSED_BITS) | 0b0010_0000 | b_flag
+    }
+
+    pub fn flags(&self) -> StatusFlags {
+        StatusFlags {
+            carry: self.carry(),
+            zero: self.zero(),
+            interrupt_disable: self.interrupt_disable(),
+            decimal: self.decimal(),
+            overflow: self.overflow(),
+            negative: self.negative(),
+        }
+    }
+
     pub fn update_flags_with_compare(&mut self, regval:u8, cmp_val:u8) {
         // Compare sets flags as if a subtraction had been carried out.
         // If regval is equal or greater than the compared value, then (C)arry will be set.
@@ -31,7 +83,8 @@ impl ProcessorStatus {
             self.clr_carry();
         }
 
-        if (regval as i8).wrapping_sub(cmp_val as i8) < 0 {
+        // N is bit 7 of the unsigned difference, not a signed comparison
+        if regval.wrapping_sub(cmp_val) & 0b1000_0000 != 0 {
             self.set_negative();
         } else {
             self.clr_negative();
@@ -105,6 +158,11 @@ impl ProcessorStatus {
     pub fn negative(&self) -> bool {
         (self.byte & 0b1000_0000) > 0
     }
+    // Only meaningful on a status byte read back off the stack:
+    // set when pushed by BRK/PHP, clear when pushed by IRQ/NMI.
+    pub fn break_bit(&self) -> bool {
+        (self.byte & 0b0001_0000) > 0
+    }
     pub fn as_byte(&self) -> u8 {
         self.byte
     }
@@ -0,0 +1,120 @@
+use core::fmt;
+
+// CPU state after an instruction, keyed by the PC it was fetched from.
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub struct InstructionRecord {
+    pub program_counter: u16,
+    pub opcode: u8,
+    pub accumulator: u8,
+    pub x: u8,
+    pub y: u8,
+    pub stack_pointer: u8,
+    pub status: u8,
+    pub cycles: u8,
+}
+
+// Expected state before the instruction at `pc` runs, as in a nestest log.
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub struct GoldenLine {
+    pub pc: u16,
+    pub a: u8,
+    pub x: u8,
+    pub y: u8,
+    pub p: u8,
+    pub sp: u8,
+    pub cycles: u64,
+}
+
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub enum TraceField {
+    ProgramCounter,
+    Opcode,
+    Accumulator,
+    X,
+    Y,
+    StackPointer,
+    Status,
+    Cycles,
+    // One trace ended before the other.
+    Length,
+}
+
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+pub struct TraceDivergence {
+    pub index: usize,
+    pub field: TraceField,
+    pub expected: Option<InstructionRecord>,
+    pub actual: Option<InstructionRecord>,
+}
+
+impl InstructionRecord {
+    // First field that differs, checked in fetch order.
+    pub fn first_difference(&self, other:&InstructionRecord) -> Option<TraceField> {
+        if self.program_counter != other.program_counter {
+            Some(TraceField::ProgramCounter)
+        } else if self.opcode != other.opcode {
+            Some(TraceField::Opcode)
+        } else if self.accumulator != other.accumulator {
+            Some(TraceField::Accumulator)
+        } else if self.x != other.x {
+            Some(TraceField::X)
+        } else if self.y != other.y {
+            Some(TraceField::Y)
+        } else if self.stack_pointer != other.stack_pointer {
+            Some(TraceField::StackPointer)
+        } else if self.status != other.status {
+            Some(TraceField::Status)
+        } else if self.cycles != other.cycles {
+            Some(TraceField::Cycles)
+        } else {
+            None
+        }
+    }
+
+    fn field_value(&self, field:TraceField) -> u16 {
+        match field {
+            TraceField::ProgramCounter => self.program_counter,
+            TraceField::Opcode => self.opcode as u16,
+            TraceField::Accumulator => self.accumulator as u16,
+            TraceField::X => self.x as u16,
+            TraceField::Y => self.y as u16,
+            TraceField::StackPointer => self.stack_pointer as u16,
+            TraceField::Status => self.status as u16,
+            TraceField::Cycles => self.cycles as u16,
+            TraceField::Length => 0,
+        }
+    }
+}
+
+// Returns the first point where `actual` departs from the golden `expected` trace.
+pub fn diff_traces(expected:&[InstructionRecord], actual:&[InstructionRecord]) -> Option<TraceDivergence> {
+    for (index, (e, a)) in expected.iter().zip(actual.iter()).enumerate() {
+        if let Some(field) = e.first_difference(a) {
+            return Some(TraceDivergence { index, field, expected: Some(*e), actual: Some(*a) });
+        }
+    }
+
+    if expected.len() != actual.len() {
+        let index = expected.len().min(actual.len());
+        return Some(TraceDivergence {
+            index,
+            field: TraceField::Length,
+            expected: expected.get(index).copied(),
+            actual: actual.get(index).copied(),
+        });
+    }
+
+    None
+}
+
+impl fmt::Display for TraceDivergence {
+    fn fmt(&self, f:&mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.field, self.expected, self.actual) {
+            (TraceField::Length, Some(_), None) => write!(f, "instruction {}: actual trace ended early", self.index),
+            (TraceField::Length, _, _) => write!(f, "instruction {}: actual trace runs past the expected trace", self.index),
+            (field, Some(e), Some(a)) => write!(f, "instruction {} at ${:04X}: {:?} expected ${:02X}, got ${:02X}",
+                self.index, e.program_counter, field, e.field_value(field), a.field_value(field)),
+            (field, _, _) => write!(f, "instruction {}: {:?} differs", self.index, field),
+        }
+    }
+}
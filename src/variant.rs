@@ -0,0 +1,24 @@
+// The CPU model being emulated. Picks which opcodes a byte decodes to, see
+// Opcode::decode.
+#[derive(Copy,Clone,Debug,PartialEq,Eq,Default)]
+pub enum Variant {
+    // The original NMOS part, with the stable illegal NOPs.
+    #[default]
+    Nmos6502,
+    // WDC/Rockwell 65C02. Runs the documented NMOS opcodes with NMOS timing,
+    // plus the CMOS additions implemented so far.
+    Cmos65C02,
+    // Ricoh 2A03/2A07 (NES): an NMOS core with the BCD circuitry cut, so the
+    // D flag can be set and pushed but ADC/SBC stay binary.
+    Nes2A03,
+    // MOS 6510 (C64): an NMOS core with a 6-bit I/O port at $0000/$0001,
+    // held by the CPU rather than the bus. See Nmos6502::io_port.
+    Mos6510,
+}
+
+impl Variant {
+    // Whether ADC/SBC honour the D flag.
+    pub fn has_decimal_mode(&self) -> bool {
+        !matches!(*self, Variant::Nes2A03)
+    }
+}
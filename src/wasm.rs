@@ -0,0 +1,136 @@
+use js_sys::Function;
+use wasm_bindgen::prelude::*;
+use crate::bus_interface::BusInterface;
+use crate::nmos6502::Nmos6502;
+
+// Bus backed by JS callbacks: `read(addr) -> byte` and `write(addr, byte)`.
+// A read that throws or returns a non-number reads as 0.
+pub struct JsBus {
+    read: Function,
+    write: Function,
+}
+
+impl BusInterface for JsBus {
+    fn get_byte_at(&mut self, addr:u16) -> u8 {
+        self.read.call1(&JsValue::NULL, &JsValue::from(addr))
+            .ok()
+            .and_then(|byte| byte.as_f64())
+            .unwrap_or(0.0) as u8
+    }
+
+    fn set_byte_at(&mut self, addr:u16, byte:u8) {
+        let _ = self.write.call2(&JsValue::NULL, &JsValue::from(addr), &JsValue::from(byte));
+    }
+}
+
+// Exported to JS as `Nmos6502`, owning its JsBus.
+#[wasm_bindgen(js_name = Nmos6502)]
+pub struct WasmNmos6502 {
+    cpu: Nmos6502,
+    bus: JsBus,
+}
+
+#[wasm_bindgen(js_class = Nmos6502)]
+impl WasmNmos6502 {
+    #[wasm_bindgen(constructor)]
+    pub fn new(read:Function, write:Function) -> Self {
+        WasmNmos6502 { cpu: Nmos6502::new(), bus: JsBus { read, write } }
+    }
+
+    pub fn reset(&mut self) {
+        self.cpu.reset(&mut self.bus);
+    }
+
+    // Returns the cycles the tick added to the clock: an instruction, an
+    // interrupt entry, or 0 for a latched reset or with count_cycles off.
+    pub fn tick(&mut self) -> u32 {
+        let before = self.cpu.total_cycles();
+        self.cpu.tick(&mut self.bus);
+        (self.cpu.total_cycles() - before) as u32
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn pc(&self) -> u16 {
+        self.cpu.get_pc()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_pc(&mut self, val:u16) {
+        self.cpu.set_pc(val);
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn a(&self) -> u8 {
+        self.cpu.get_a()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_a(&mut self, val:u8) {
+        self.cpu.set_a(val);
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn x(&self) -> u8 {
+        self.cpu.get_x()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_x(&mut self, val:u8) {
+        self.cpu.set_x(val);
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn y(&self) -> u8 {
+        self.cpu.get_y()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_y(&mut self, val:u8) {
+        self.cpu.set_y(val);
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn sp(&self) -> u8 {
+        self.cpu.get_stack_pointer()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_sp(&mut self, val:u8) {
+        self.cpu.set_stack_pointer(val);
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn status(&self) -> u8 {
+        self.cpu.get_status()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_status(&mut self, val:u8) {
+        self.cpu.set_status(val);
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn irq(&self) -> bool {
+        self.cpu.irq
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_irq(&mut self, val:bool) {
+        self.cpu.irq = val;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn nmi(&self) -> bool {
+        self.cpu.nmi
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_nmi(&mut self, val:bool) {
+        self.cpu.nmi = val;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn halted(&self) -> bool {
+        self.cpu.halted
+    }
+}
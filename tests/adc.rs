@@ -0,0 +1,39 @@
+// Binary ADC for every A, operand and carry in.
+mod common;
+
+use common::*;
+use nmos6502::asm::Assembler;
+use nmos6502::nmos6502::Nmos6502;
+
+// A and the N, V, Z, C bits of `a + operand + carry`, from the unsigned and
+// signed sums.
+fn reference(a:u8, operand:u8, carry:bool) -> (u8, u8) {
+    let unsigned = a as u16 + operand as u16 + carry as u16;
+    let signed = a as i8 as i16 + operand as i8 as i16 + carry as i16;
+    let result = unsigned as u8;
+    let mut flags = result & N;
+    if !(-128..=127).contains(&signed) { flags |= V; }
+    if result == 0 { flags |= Z; }
+    if unsigned > 0xFF { flags |= C; }
+    (result, flags)
+}
+
+#[test]
+fn adc_matches_the_reference() {
+    let program = Assembler::new().adc_imm(0).assemble();
+    for carry in [false, true] {
+        let status = STATUS_CLEAR | if carry { C } else { 0 };
+        sweep_immediate(&program, status, Nmos6502::set_a, |cpu, a, operand| {
+            let actual = (cpu.get_a(), cpu.get_status() & (N | V | Z | C));
+            assert_eq!(actual, reference(a, operand, carry), "A=${:02X} #${:02X} C={}", a, operand, carry);
+        });
+    }
+}
+
+#[test]
+fn reference_spot_checks() {
+    assert_eq!(reference(0x7F, 0x01, false), (0x80, N | V));
+    assert_eq!(reference(0x80, 0x80, false), (0x00, V | Z | C));
+    assert_eq!(reference(0xFF, 0x00, true), (0x00, Z | C));
+    assert_eq!(reference(0x50, 0x10, false), (0x60, 0));
+}
@@ -0,0 +1,43 @@
+// Branch offsets are relative to the address after the 2-byte branch, and
+// wrap around the address space.
+mod common;
+
+use common::*;
+use nmos6502::asm::Assembler;
+
+// PC after a taken BEQ at `at` with `offset`.
+fn branch_from(at:u16, offset:i8) -> u16 {
+    let (mut cpu, mut bus) = setup(&[], STATUS_CLEAR | Z);
+    bus.load(at, &Assembler::new().beq(offset).assemble());
+    cpu.set_pc(at);
+    step(&mut cpu, &mut bus);
+    cpu.get_pc()
+}
+
+#[test]
+fn backward_offset_wraps_below_zero() {
+    // $0002 - 128
+    assert_eq!(branch_from(0x0000, -128), 0xFF82);
+}
+
+#[test]
+fn forward_offsets_land_after_the_operand() {
+    assert_eq!(branch_from(0x0400, 127), 0x0481);
+    assert_eq!(branch_from(0x0400, 0), 0x0402);
+    assert_eq!(branch_from(0x0400, -2), 0x0400);
+}
+
+#[test]
+fn forward_offset_wraps_past_ffff() {
+    // $FF80 + 2 + 127
+    assert_eq!(branch_from(0xFF80, 127), 0x0001);
+    // operand at $FFFF, so the offset applies from $0000
+    assert_eq!(branch_from(0xFFFE, 0x10), 0x0010);
+}
+
+#[test]
+fn untaken_branch_falls_through() {
+    let (mut cpu, mut bus) = setup(&Assembler::new().bne(-128).assemble(), STATUS_CLEAR | Z);
+    assert_eq!(step(&mut cpu, &mut bus), 2);
+    assert_eq!(cpu.get_pc(), ORIGIN + 2);
+}
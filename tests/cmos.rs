@@ -0,0 +1,234 @@
+// The 65C02's added instructions, and that the NMOS part doesn't have them.
+mod common;
+
+use common::*;
+use nmos6502::asm::Assembler;
+use nmos6502::bus_interface::BusInterface;
+use nmos6502::flat_bus::FlatBus;
+use nmos6502::nmos6502::{HaltReason, Nmos6502};
+use nmos6502::opcodes::Opcode;
+use nmos6502::variant::Variant;
+
+// A 65C02 at ORIGIN with `program` loaded there.
+fn cmos(program:&[u8]) -> (Nmos6502, FlatBus) {
+    let (mut cpu, bus) = setup(program, STATUS_CLEAR);
+    cpu.variant = Variant::Cmos65C02;
+    (cpu, bus)
+}
+
+// RMBn, SMBn, BBRn and BBSn for bit n.
+fn rmb(bit:u8) -> u8 { 0x07 | bit << 4 }
+fn smb(bit:u8) -> u8 { 0x87 | bit << 4 }
+fn bbr(bit:u8) -> u8 { 0x0F | bit << 4 }
+fn bbs(bit:u8) -> u8 { 0x8F | bit << 4 }
+
+#[test]
+fn rmb_and_smb_change_one_bit_in_five_cycles() {
+    for bit in 0..8 {
+        let (mut cpu, mut bus) = cmos(&[rmb(bit), 0x10, smb(bit), 0x11]);
+        bus[0x0010] = 0xFF;
+        bus[0x0011] = 0x00;
+        assert_eq!(step(&mut cpu, &mut bus), 5, "RMB{}", bit);
+        assert_eq!(bus[0x0010], !(1 << bit), "RMB{}", bit);
+        assert_eq!(step(&mut cpu, &mut bus), 5, "SMB{}", bit);
+        assert_eq!(bus[0x0011], 1 << bit, "SMB{}", bit);
+        // no flags change
+        assert_eq!(cpu.get_status(), STATUS_CLEAR);
+    }
+}
+
+// PC and cycles after branch `opcode` tests a zero page byte of `value`.
+fn bit_branch(opcode:u8, value:u8, offset:u8) -> (u16, u64) {
+    let (mut cpu, mut bus) = cmos(&[opcode, 0x10, offset]);
+    bus[0x0010] = value;
+    let cycles = step(&mut cpu, &mut bus);
+    (cpu.get_pc(), cycles)
+}
+
+#[test]
+fn bbr_and_bbs_branch_on_one_bit() {
+    let next = ORIGIN + 3;
+    for bit in 0..8 {
+        let (set, clear) = (1 << bit, !(1 << bit));
+        // not taken, taken, taken across a page
+        assert_eq!(bit_branch(bbr(bit), set, 0x10), (next, 5), "BBR{}", bit);
+        assert_eq!(bit_branch(bbr(bit), clear, 0x10), (next + 0x10, 6), "BBR{}", bit);
+        assert_eq!(bit_branch(bbr(bit), clear, 0xF0), (next - 0x10, 7), "BBR{}", bit);
+        assert_eq!(bit_branch(bbs(bit), clear, 0x10), (next, 5), "BBS{}", bit);
+        assert_eq!(bit_branch(bbs(bit), set, 0x10), (next + 0x10, 6), "BBS{}", bit);
+        assert_eq!(bit_branch(bbs(bit), set, 0xF0), (next - 0x10, 7), "BBS{}", bit);
+    }
+}
+
+#[test]
+fn nmos_has_no_bit_instructions() {
+    for bit in 0..8 {
+        for opcode in [rmb(bit), smb(bit), bbr(bit), bbs(bit)] {
+            assert_eq!(Opcode::decode(opcode, Variant::Nmos6502), Opcode::UNREC, "${:02X}", opcode);
+            let (mut cpu, mut bus) = setup(&[opcode, 0x10, 0x10], STATUS_CLEAR);
+            bus[0x0010] = 0x55;
+            step(&mut cpu, &mut bus);
+            assert_eq!((cpu.get_pc(), bus[0x0010]), (ORIGIN + 1, 0x55), "${:02X}", opcode);
+
+            let (mut cpu, mut bus) = setup(&[opcode, 0x10, 0x10], STATUS_CLEAR);
+            cpu.strict_illegal = true;
+            step(&mut cpu, &mut bus);
+            assert_eq!(cpu.halt_reason(), Some(HaltReason::UnknownOpcode(opcode, ORIGIN)), "${:02X}", opcode);
+        }
+    }
+}
+
+// TSB/TRB $10 and $1234 opcodes.
+const TSB_ZP: u8 = 0x04;
+const TSB_ABS: u8 = 0x0C;
+const TRB_ZP: u8 = 0x14;
+const TRB_ABS: u8 = 0x1C;
+
+// Memory, cycles and status after `program` runs with A and the operand byte
+// at $10 and $1234 set to `memory`.
+fn test_bits(program:&[u8], a:u8, memory:u8, status:u8) -> (u8, u64, u8) {
+    let (mut cpu, mut bus) = cmos(program);
+    bus[0x0010] = memory;
+    bus[0x1234] = memory;
+    cpu.set_a(a);
+    cpu.set_status(status);
+    let cycles = step(&mut cpu, &mut bus);
+    let addr = if program.len() == 2 { 0x0010 } else { 0x1234 };
+    (bus[addr], cycles, cpu.get_status())
+}
+
+#[test]
+fn tsb_and_trb_set_and_clear_the_bits_of_a() {
+    for (program, cycles) in [(&[TSB_ZP, 0x10][..], 5), (&[TSB_ABS, 0x34, 0x12][..], 6)] {
+        // Z from A AND memory; N and V untouched, unlike BIT
+        assert_eq!(test_bits(program, 0x0F, 0xF0, STATUS_CLEAR), (0xFF, cycles, STATUS_CLEAR | Z));
+        assert_eq!(test_bits(program, 0x0F, 0x3C, STATUS_CLEAR | N | V | Z), (0x3F, cycles, STATUS_CLEAR | N | V));
+    }
+    for (program, cycles) in [(&[TRB_ZP, 0x10][..], 5), (&[TRB_ABS, 0x34, 0x12][..], 6)] {
+        assert_eq!(test_bits(program, 0x0F, 0xF0, STATUS_CLEAR), (0xF0, cycles, STATUS_CLEAR | Z));
+        assert_eq!(test_bits(program, 0x0F, 0x3C, STATUS_CLEAR | N | V | Z), (0x30, cycles, STATUS_CLEAR | N | V));
+    }
+}
+
+// Logs every access to $10 as (value, is_write).
+struct AccessLog {
+    memory: FlatBus,
+    accesses: Vec<(u8, bool)>,
+}
+
+impl BusInterface for AccessLog {
+    fn get_byte_at(&mut self, addr:u16) -> u8 {
+        self.memory[addr]
+    }
+
+    fn set_byte_at(&mut self, addr:u16, byte:u8) {
+        self.memory[addr] = byte;
+    }
+
+    fn on_bus_access(&mut self, addr:u16, value:u8, is_write:bool) {
+        if addr == 0x0010 {
+            self.accesses.push((value, is_write));
+        }
+    }
+}
+
+fn rmw_accesses(program:&[u8], variant:Variant) -> Vec<(u8, bool)> {
+    let (mut cpu, memory) = setup(program, STATUS_CLEAR);
+    let mut bus = AccessLog { memory, accesses: Vec::new() };
+    bus.memory[0x0010] = 0x05;
+    cpu.variant = variant;
+    cpu.set_a(0x02);
+    cpu.tick(&mut bus);
+    bus.accesses
+}
+
+#[test]
+fn read_modify_write_rereads_instead_of_writing_twice() {
+    let inc = Assembler::new().inc_zp(0x10).assemble();
+    // NMOS writes the old value back before the new one
+    assert_eq!(rmw_accesses(&inc, Variant::Nmos6502), [(0x05, false), (0x05, true), (0x06, true)]);
+    assert_eq!(rmw_accesses(&inc, Variant::Cmos65C02), [(0x05, false), (0x05, false), (0x06, true)]);
+    assert_eq!(rmw_accesses(&[TSB_ZP, 0x10], Variant::Cmos65C02), [(0x05, false), (0x05, false), (0x07, true)]);
+    assert_eq!(rmw_accesses(&[TRB_ZP, 0x10], Variant::Cmos65C02), [(0x05, false), (0x05, false), (0x05, true)]);
+}
+
+// A, the byte at $1234 and the cycles after `opcode` ($10) runs with A=$0F,
+// C set, and a pointer at $10 to $1234 holding $3C.
+fn zp_indirect(opcode:u8) -> (u8, u8, u64) {
+    let (mut cpu, mut bus) = cmos(&[opcode, 0x10]);
+    bus.load(0x0010, &[0x34, 0x12]);
+    bus[0x1234] = 0x3C;
+    cpu.set_a(0x0F);
+    cpu.set_status(STATUS_CLEAR | C);
+    let cycles = step(&mut cpu, &mut bus);
+    (cpu.get_a(), bus[0x1234], cycles)
+}
+
+#[test]
+fn zero_page_indirect_alu_ops() {
+    let ops = [
+        ("ORA", 0x12, 0x3F, 0x3C),
+        ("AND", 0x32, 0x0C, 0x3C),
+        ("EOR", 0x52, 0x33, 0x3C),
+        ("ADC", 0x72, 0x4C, 0x3C),
+        ("STA", 0x92, 0x0F, 0x0F),
+        ("LDA", 0xB2, 0x3C, 0x3C),
+        ("CMP", 0xD2, 0x0F, 0x3C),
+        ("SBC", 0xF2, 0xD3, 0x3C),
+    ];
+    for (name, opcode, a, memory) in ops {
+        assert_eq!(zp_indirect(opcode), (a, memory, 5), "{} ($10)", name);
+        assert_eq!(Opcode::decode(opcode, Variant::Nmos6502), Opcode::UNREC, "{} ($10)", name);
+    }
+}
+
+#[test]
+fn zero_page_indirect_pointer_wraps_within_zero_page() {
+    let (mut cpu, mut bus) = cmos(&[0xB2, 0xFF]);
+    // high byte from $00, not $0100
+    bus[0x00FF] = 0x34;
+    bus[0x0000] = 0x12;
+    bus[0x0100] = 0x56;
+    bus[0x1234] = 0x42;
+    bus[0x5634] = 0x99;
+    assert_eq!(step(&mut cpu, &mut bus), 5);
+    assert_eq!(cpu.get_a(), 0x42);
+}
+
+// PC and cycles after JMP ($10FF) with the pointer's high byte candidates
+// at $1000 and $1100.
+fn jmp_indirect_at_page_end(variant:Variant) -> (u16, u64) {
+    let (mut cpu, mut bus) = setup(&Assembler::new().jmp_ind(0x10FF).assemble(), STATUS_CLEAR);
+    cpu.variant = variant;
+    bus[0x10FF] = 0x34;
+    bus[0x1000] = 0x12;
+    bus[0x1100] = 0x56;
+    let cycles = step(&mut cpu, &mut bus);
+    (cpu.get_pc(), cycles)
+}
+
+#[test]
+fn jmp_indirect_page_wrap_is_nmos_only() {
+    // NMOS takes the high byte from the start of the same page
+    assert_eq!(jmp_indirect_at_page_end(Variant::Nmos6502), (0x1234, 5));
+    // the 65C02 fix reads the next page, and costs a cycle
+    assert_eq!(jmp_indirect_at_page_end(Variant::Cmos65C02), (0x5634, 6));
+}
+
+#[test]
+fn jmp_absolute_indexed_indirect() {
+    // JMP ($10FE,X): the pointer crosses into the next page without wrapping
+    for (x, pointer) in [(0x00, 0x10FE), (0x01, 0x10FF), (0x02, 0x1100)] {
+        let (mut cpu, mut bus) = cmos(&[0x7C, 0xFE, 0x10]);
+        bus.load(0x10FE, &[0x34, 0x12, 0x56, 0x78]);
+        cpu.set_x(x);
+        let target = u16::from_le_bytes([bus[pointer], bus[pointer + 1]]);
+        assert_eq!(step(&mut cpu, &mut bus), 6, "X=${:02X}", x);
+        assert_eq!(cpu.get_pc(), target, "X=${:02X}", x);
+    }
+
+    // a three-byte NOP on NMOS
+    let (mut cpu, mut bus) = setup(&[0x7C, 0xFE, 0x10], STATUS_CLEAR);
+    step(&mut cpu, &mut bus);
+    assert_eq!(cpu.get_pc(), ORIGIN + 3);
+}
@@ -0,0 +1,54 @@
+// Shared setup for the integration tests. Not every test file uses every helper.
+#![allow(dead_code)]
+
+use nmos6502::flat_bus::FlatBus;
+use nmos6502::nmos6502::Nmos6502;
+
+// Where test programs are loaded and started.
+pub const ORIGIN: u16 = 0x0400;
+
+// Status with only the always-set bits 4 and 5.
+pub const STATUS_CLEAR: u8 = 0x30;
+
+// Bits of the status byte, as pushed by PHP.
+pub const C: u8 = 0x01;
+pub const Z: u8 = 0x02;
+pub const I: u8 = 0x04;
+pub const D: u8 = 0x08;
+pub const V: u8 = 0x40;
+pub const N: u8 = 0x80;
+
+// A CPU at ORIGIN with `program` loaded there.
+pub fn setup(program:&[u8], status:u8) -> (Nmos6502, FlatBus) {
+    let mut bus = FlatBus::new();
+    bus.load(ORIGIN, program);
+    let cpu = Nmos6502::with_state(ORIGIN, 0, 0, 0, 0xFF, status);
+    (cpu, bus)
+}
+
+// Runs `count` instructions, returning the cycles they took.
+pub fn run(cpu:&mut Nmos6502, bus:&mut FlatBus, count:u32) -> u64 {
+    cpu.step_n(bus, count)
+}
+
+// Runs one instruction and returns its cycle count.
+pub fn step(cpu:&mut Nmos6502, bus:&mut FlatBus) -> u64 {
+    run(cpu, bus, 1)
+}
+
+// Runs `program`, one immediate-mode instruction at ORIGIN, for every register
+// value and operand. `load` puts the register value in place and `check` sees
+// the CPU afterwards, with the register value and operand.
+pub fn sweep_immediate(program:&[u8], status:u8, load:impl Fn(&mut Nmos6502, u8), mut check:impl FnMut(&Nmos6502, u8, u8)) {
+    let (mut cpu, mut bus) = setup(program, status);
+    for reg in 0..=0xFFu8 {
+        for operand in 0..=0xFFu8 {
+            bus[ORIGIN + 1] = operand;
+            cpu.set_pc(ORIGIN);
+            load(&mut cpu, reg);
+            cpu.set_status(status);
+            step(&mut cpu, &mut bus);
+            check(&cpu, reg, operand);
+        }
+    }
+}
@@ -0,0 +1,53 @@
+// CMP, CPX and CPY at the unsigned and signed boundaries.
+mod common;
+
+use common::*;
+use nmos6502::asm::Assembler;
+use nmos6502::nmos6502::Nmos6502;
+
+// (register, operand, N Z C afterwards)
+const CASES: [(u8, u8, u8); 14] = [
+    (0x00, 0x00, Z | C),
+    (0x00, 0x01, N),
+    (0x01, 0x00, C),
+    (0x00, 0xFF, 0),
+    (0xFF, 0x00, N | C),
+    (0xFF, 0xFF, Z | C),
+    (0x7F, 0x7F, Z | C),
+    (0x7F, 0x80, N),
+    (0x80, 0x7F, C),
+    (0x80, 0x00, N | C),
+    (0x80, 0x01, C),
+    (0x01, 0x80, N),
+    (0xFF, 0x80, C),
+    (0x80, 0xFF, N),
+];
+
+// Runs `program`, a one-byte-operand compare, over CASES with the register
+// set and read by `set` and `get`.
+fn check_cases(name:&str, program:Assembler, set:fn(&mut Nmos6502, u8), get:fn(&Nmos6502) -> u8) {
+    for (reg, operand, flags) in CASES {
+        let (mut cpu, mut bus) = setup(&program.clone().assemble(), STATUS_CLEAR | V);
+        bus[ORIGIN + 1] = operand;
+        set(&mut cpu, reg);
+        step(&mut cpu, &mut bus);
+        // V is left alone
+        assert_eq!(cpu.get_status(), STATUS_CLEAR | V | flags, "{} ${:02X} #${:02X}", name, reg, operand);
+        assert_eq!(get(&cpu), reg, "{} ${:02X} #${:02X}", name, reg, operand);
+    }
+}
+
+#[test]
+fn cmp_at_the_boundaries() {
+    check_cases("CMP", Assembler::new().cmp_imm(0), Nmos6502::set_a, Nmos6502::get_a);
+}
+
+#[test]
+fn cpx_at_the_boundaries() {
+    check_cases("CPX", Assembler::new().cpx_imm(0), Nmos6502::set_x, Nmos6502::get_x);
+}
+
+#[test]
+fn cpy_at_the_boundaries() {
+    check_cases("CPY", Assembler::new().cpy_imm(0), Nmos6502::set_y, Nmos6502::get_y);
+}
@@ -0,0 +1,41 @@
+// Turning count_cycles off only skips the cycle bookkeeping; registers and
+// memory must evolve exactly as with it on.
+mod common;
+
+use common::*;
+use nmos6502::flat_bus::FlatBus;
+use nmos6502::nmos6502::Nmos6502;
+
+fn random_memory(seed:u64) -> Vec<u8> {
+    let mut state = seed;
+    (0..0x10000).map(|_| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state as u8
+    }).collect()
+}
+
+#[test]
+fn architectural_state_is_unaffected() {
+    for seed in 1..=20u64 {
+        let memory = random_memory(seed * 0x9E37_79B9);
+        let (mut counted_bus, mut uncounted_bus) = (FlatBus::new(), FlatBus::new());
+        counted_bus.load(0, &memory);
+        uncounted_bus.load(0, &memory);
+        let mut counted = Nmos6502::with_state(ORIGIN, 0, 0, 0, 0xFF, STATUS_CLEAR);
+        let mut uncounted = Nmos6502::with_state(ORIGIN, 0, 0, 0, 0xFF, STATUS_CLEAR);
+        uncounted.count_cycles = false;
+
+        for instruction in 0..5000 {
+            counted.tick(&mut counted_bus);
+            uncounted.tick(&mut uncounted_bus);
+            assert_eq!(counted.state_hash(), uncounted.state_hash(), "seed {} instruction {}", seed, instruction);
+            assert_eq!(counted.halted, uncounted.halted);
+            assert_eq!(uncounted.last_pc_cycles, 0);
+        }
+        assert!((0..=0xFFFF).all(|addr| counted_bus[addr] == uncounted_bus[addr]), "seed {}: memory differs", seed);
+        assert_eq!(uncounted.total_cycles(), 0);
+        assert_eq!(counted.total_instructions(), uncounted.total_instructions());
+    }
+}
@@ -0,0 +1,77 @@
+// total_cycles is a real-time clock: every tick that does work, interrupt
+// entry included, advances it by the cycles spent.
+mod common;
+
+use common::*;
+use nmos6502::asm::Assembler;
+use nmos6502::flat_bus::FlatBus;
+use nmos6502::nmos6502::{CycleBreakdown, Nmos6502};
+
+const IRQ_HANDLER: u16 = 0x8000;
+const NMI_HANDLER: u16 = 0x9000;
+
+fn setup_with_vectors() -> (Nmos6502, FlatBus) {
+    let (cpu, mut bus) = setup(&Assembler::new().nop().nop().nop().nop().assemble(), STATUS_CLEAR);
+    bus.load(0xFFFA, &NMI_HANDLER.to_le_bytes());
+    bus.load(0xFFFE, &IRQ_HANDLER.to_le_bytes());
+    bus.load(IRQ_HANDLER, &Assembler::new().nop().assemble());
+    bus.load(NMI_HANDLER, &Assembler::new().nop().assemble());
+    (cpu, bus)
+}
+
+#[test]
+fn irq_entry_takes_seven_cycles() {
+    let (mut cpu, mut bus) = setup_with_vectors();
+    assert_eq!(step(&mut cpu, &mut bus), 2);
+    cpu.irq = true;
+    let before = cpu.total_cycles();
+    cpu.tick(&mut bus);
+    assert_eq!(cpu.get_pc(), IRQ_HANDLER);
+    assert_eq!(cpu.total_cycles() - before, 7);
+    assert_eq!(cpu.last_pc_cycles, 7);
+    assert_eq!(cpu.last_cycle_breakdown(), CycleBreakdown { base: 7, branch_penalty: 0, page_cross_penalty: 0 });
+}
+
+#[test]
+fn nmi_entry_takes_seven_cycles() {
+    let (mut cpu, mut bus) = setup_with_vectors();
+    step(&mut cpu, &mut bus);
+    cpu.nmi = true;
+    assert_eq!(step(&mut cpu, &mut bus), 7);
+    assert_eq!(cpu.get_pc(), NMI_HANDLER);
+}
+
+#[test]
+fn clock_counts_interrupts_and_instructions() {
+    let (mut cpu, mut bus) = setup_with_vectors();
+    // NOP, IRQ entry, NOP in the handler
+    step(&mut cpu, &mut bus);
+    cpu.irq = true;
+    step(&mut cpu, &mut bus);
+    cpu.irq = false;
+    step(&mut cpu, &mut bus);
+    assert_eq!(cpu.total_cycles(), 2 + 7 + 2);
+    assert_eq!(cpu.total_instructions(), 2);
+}
+
+#[test]
+fn scheduled_irq_fires_on_the_clock() {
+    let (mut cpu, mut bus) = setup_with_vectors();
+    cpu.schedule_irq(4);
+    // two NOPs reach cycle 4, then the IRQ is taken
+    run(&mut cpu, &mut bus, 2);
+    assert_eq!(cpu.total_cycles(), 4);
+    step(&mut cpu, &mut bus);
+    assert_eq!(cpu.get_pc(), IRQ_HANDLER);
+    assert_eq!(cpu.total_cycles(), 11);
+}
+
+#[test]
+fn reset_counters_restarts_the_clock() {
+    let (mut cpu, mut bus) = setup_with_vectors();
+    run(&mut cpu, &mut bus, 3);
+    cpu.reset_counters();
+    assert_eq!((cpu.total_cycles(), cpu.total_instructions()), (0, 0));
+    step(&mut cpu, &mut bus);
+    assert_eq!((cpu.total_cycles(), cpu.total_instructions()), (2, 1));
+}
@@ -0,0 +1,168 @@
+// Cycle counts, bus reads and zero page wrapping of loads, stores and fetches.
+mod common;
+
+use common::*;
+use nmos6502::asm::Assembler;
+use nmos6502::bus_interface::BusInterface;
+use nmos6502::nmos6502::Nmos6502;
+use nmos6502::variant::Variant;
+
+// Cycles for `program`'s first instruction with the given X and Y, and a
+// pointer at $10 to `pointer`.
+fn cycles(program:Assembler, x:u8, y:u8, pointer:u16) -> u64 {
+    let (mut cpu, mut bus) = setup(&program.assemble(), STATUS_CLEAR);
+    bus.load(0x0010, &pointer.to_le_bytes());
+    cpu.set_x(x);
+    cpu.set_y(y);
+    step(&mut cpu, &mut bus)
+}
+
+#[test]
+fn sta_indirect_y_is_always_six() {
+    assert_eq!(cycles(Assembler::new().sta_ind_y(0x10), 0, 0x01, 0x2000), 6);
+    assert_eq!(cycles(Assembler::new().sta_ind_y(0x10), 0, 0x20, 0x20F0), 6);
+}
+
+#[test]
+fn lda_indirect_y_pays_for_a_page_cross() {
+    assert_eq!(cycles(Assembler::new().lda_ind_y(0x10), 0, 0x01, 0x2000), 5);
+    assert_eq!(cycles(Assembler::new().lda_ind_y(0x10), 0, 0x20, 0x20F0), 6);
+}
+
+#[test]
+fn sta_absolute_indexed_is_always_five() {
+    for index in [0x00, 0x07, 0x08, 0xFF] {
+        assert_eq!(cycles(Assembler::new().sta_abs_x(0x12F8), index, 0, 0), 5, "STA $12F8,X X=${:02X}", index);
+        assert_eq!(cycles(Assembler::new().sta_abs_y(0x12F8), 0, index, 0), 5, "STA $12F8,Y Y=${:02X}", index);
+    }
+}
+
+#[test]
+fn lda_absolute_indexed_pays_for_a_page_cross() {
+    assert_eq!(cycles(Assembler::new().lda_abs_x(0x12F8), 0x07, 0, 0), 4);
+    assert_eq!(cycles(Assembler::new().lda_abs_x(0x12F8), 0x08, 0, 0), 5);
+}
+
+// Logs reads, to see the dummy read a store makes before its write. `reads`
+// are the ones the CPU reports, `fetched` the ones it actually made.
+struct LoggingBus {
+    memory: Vec<u8>,
+    reads: Vec<u16>,
+    fetched: Vec<u16>,
+}
+
+impl LoggingBus {
+    fn new(program:&[u8]) -> Self {
+        let mut memory = vec![0; 0x10000];
+        memory[ORIGIN as usize..ORIGIN as usize + program.len()].copy_from_slice(program);
+        LoggingBus { memory, reads: Vec::new(), fetched: Vec::new() }
+    }
+}
+
+impl BusInterface for LoggingBus {
+    fn get_byte_at(&mut self, addr:u16) -> u8 {
+        self.fetched.push(addr);
+        self.memory[addr as usize]
+    }
+
+    fn set_byte_at(&mut self, addr:u16, byte:u8) {
+        self.memory[addr as usize] = byte;
+    }
+
+    fn on_bus_access(&mut self, addr:u16, _value:u8, is_write:bool) {
+        if !is_write {
+            self.reads.push(addr);
+        }
+    }
+}
+
+#[test]
+fn sta_absolute_indexed_reads_the_unfixed_address() {
+    let program = Assembler::new().sta_abs_x(0x12F8).assemble();
+    for (x, dummy) in [(0x00, 0x12F8), (0x0F, 0x1207)] {
+        let mut bus = LoggingBus::new(&program);
+        let mut cpu = Nmos6502::with_state(ORIGIN, 0x42, x, 0, 0xFF, STATUS_CLEAR);
+        cpu.tick(&mut bus);
+        // opcode and operand fetches, then the dummy read
+        assert_eq!(bus.reads, [ORIGIN, ORIGIN + 1, ORIGIN + 2, dummy]);
+        assert_eq!(bus.memory[0x12F8 + x as usize], 0x42);
+    }
+}
+
+#[test]
+fn fetch_reads_only_the_operands_the_variant_uses() {
+    // BBR0 $10,$20 on the 65C02, a one-byte unknown opcode on the NMOS part
+    for (variant, fetch) in [(Variant::Nmos6502, &[ORIGIN][..]), (Variant::Cmos65C02, &[ORIGIN, ORIGIN + 1, ORIGIN + 2][..])] {
+        let mut bus = LoggingBus::new(&[0x0F, 0x10, 0x20]);
+        let mut cpu = Nmos6502::with_state(ORIGIN, 0, 0, 0, 0xFF, STATUS_CLEAR);
+        cpu.variant = variant;
+        cpu.tick(&mut bus);
+        assert_eq!(&bus.fetched[..fetch.len()], fetch, "{:?}", variant);
+        assert_eq!(bus.fetched, bus.reads, "{:?}", variant);
+    }
+}
+
+#[test]
+fn every_read_is_reported() {
+    // not the 6510, whose port answers reads of $0000/$0001 without the bus
+    for variant in [Variant::Nmos6502, Variant::Cmos65C02, Variant::Nes2A03] {
+        for opcode in 0..=0xFFu8 {
+            let mut bus = LoggingBus::new(&[opcode, 0x10, 0x20]);
+            let mut cpu = Nmos6502::with_state(ORIGIN, 0, 0, 0, 0xFF, STATUS_CLEAR);
+            cpu.variant = variant;
+            cpu.tick(&mut bus);
+            assert_eq!(bus.fetched, bus.reads, "${:02X} on {:?}", opcode, variant);
+        }
+    }
+}
+
+#[test]
+fn zero_page_x_read_modify_write_wraps_in_six() {
+    let programs = [
+        ("ASL", Assembler::new().asl_zp_x(0xFF), 0x42 << 1),
+        ("LSR", Assembler::new().lsr_zp_x(0xFF), 0x42 >> 1),
+        ("ROL", Assembler::new().rol_zp_x(0xFF), 0x42 << 1),
+        ("ROR", Assembler::new().ror_zp_x(0xFF), 0x42 >> 1),
+        ("INC", Assembler::new().inc_zp_x(0xFF), 0x43),
+        ("DEC", Assembler::new().dec_zp_x(0xFF), 0x41),
+    ];
+    for (name, program, result) in programs {
+        for x in [0x00, 0x01, 0x80] {
+            let (mut cpu, mut bus) = setup(&program.clone().assemble(), STATUS_CLEAR);
+            // $FF + X wraps to $00 + X - 1, never into page one
+            let target = 0xFFu8.wrapping_add(x) as u16;
+            bus[target] = 0x42;
+            bus[target + 0x0100] = 0x42;
+            cpu.set_x(x);
+            assert_eq!(step(&mut cpu, &mut bus), 6, "{} $FF,X X=${:02X}", name, x);
+            assert_eq!(bus[target], result, "{} $FF,X X=${:02X}", name, x);
+            assert_eq!(bus[target + 0x0100], 0x42, "{} $FF,X X=${:02X}", name, x);
+        }
+    }
+}
+
+#[test]
+fn indexed_indirect_pointer_wraps_within_zero_page() {
+    let (mut cpu, mut bus) = setup(&Assembler::new().lda_ind_x(0xFF).assemble(), STATUS_CLEAR);
+    // pointer low byte at $FF, high byte at $00; $0100 holds a decoy
+    bus[0x00FF] = 0x34;
+    bus[0x0000] = 0x12;
+    bus[0x0100] = 0x56;
+    bus[0x1234] = 0x42;
+    bus[0x5634] = 0x99;
+    assert_eq!(step(&mut cpu, &mut bus), 6);
+    assert_eq!(cpu.get_a(), 0x42);
+}
+
+#[test]
+fn indirect_indexed_pointer_wraps_within_zero_page() {
+    let (mut cpu, mut bus) = setup(&Assembler::new().lda_ind_y(0xFF).assemble(), STATUS_CLEAR);
+    bus[0x00FF] = 0x30;
+    bus[0x0000] = 0x12;
+    bus[0x0100] = 0x56;
+    bus[0x1234] = 0x42;
+    bus[0x5634] = 0x99;
+    cpu.set_y(0x04);
+    assert_eq!(step(&mut cpu, &mut bus), 5);
+    assert_eq!(cpu.get_a(), 0x42);
+}
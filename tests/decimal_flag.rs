@@ -0,0 +1,103 @@
+// The decimal flag only changes ADC and SBC, and not even those on the 2A03;
+// everything else does binary math with D set.
+mod common;
+
+use common::*;
+use nmos6502::asm::Assembler;
+use nmos6502::nmos6502::Nmos6502;
+use nmos6502::variant::Variant;
+
+// N, Z and C a binary compare of `reg` with `operand` leaves.
+fn binary_compare_flags(reg:u8, operand:u8) -> u8 {
+    let mut flags = reg.wrapping_sub(operand) & N;
+    if reg == operand { flags |= Z; }
+    if reg >= operand { flags |= C; }
+    flags
+}
+
+#[test]
+fn cmp_is_binary_with_decimal_set() {
+    // $10 - $90 is $80 in binary but $20 with a borrow in BCD, so N tells them apart.
+    let (mut cpu, mut bus) = setup(&Assembler::new().lda_imm(0x10).cmp_imm(0x90).assemble(), STATUS_CLEAR | D);
+    run(&mut cpu, &mut bus, 2);
+    assert_eq!(cpu.get_status() & (N | Z | C), N);
+    assert_eq!(cpu.get_a(), 0x10);
+}
+
+#[test]
+fn cmp_matches_binary_compare_for_every_operand() {
+    sweep_immediate(&Assembler::new().cmp_imm(0).assemble(), STATUS_CLEAR | D, Nmos6502::set_a, |cpu, a, operand| {
+        assert_eq!(cpu.get_status() & (N | Z | C), binary_compare_flags(a, operand), "CMP A=${:02X} #${:02X}", a, operand);
+        assert_ne!(cpu.get_status() & D, 0);
+    });
+}
+
+// A, X, Y, the flags other than D, and the bytes at $10 and $0210 after running
+// `program` once with all of those set to `value`.
+fn outcome(program:&[u8], value:u8, status:u8) -> [u8; 6] {
+    let (mut cpu, mut bus) = setup(program, status);
+    cpu.set_a(value);
+    cpu.set_x(value);
+    cpu.set_y(value);
+    bus[0x0010] = value;
+    bus[0x0210] = value;
+    step(&mut cpu, &mut bus);
+    [cpu.get_a(), cpu.get_x(), cpu.get_y(), cpu.get_status() & !D, bus[0x0010], bus[0x0210]]
+}
+
+#[test]
+fn increments_and_decrements_ignore_decimal() {
+    // which slot of the outcome each one changes, and by how much
+    let programs = [
+        ("INC zp", Assembler::new().inc_zp(0x10), 4, 1),
+        ("DEC zp", Assembler::new().dec_zp(0x10), 4, -1),
+        ("INC abs", Assembler::new().inc_abs(0x0210), 5, 1),
+        ("DEC abs", Assembler::new().dec_abs(0x0210), 5, -1),
+        ("INX", Assembler::new().inx(), 1, 1),
+        ("INY", Assembler::new().iny(), 2, 1),
+        ("DEX", Assembler::new().dex(), 1, -1),
+        ("DEY", Assembler::new().dey(), 2, -1),
+    ];
+    for (name, program, slot, delta) in programs {
+        let program = program.assemble();
+        for value in 0..=0xFFu8 {
+            let decimal = outcome(&program, value, STATUS_CLEAR | D);
+            assert_eq!(decimal, outcome(&program, value, STATUS_CLEAR), "{} ${:02X}", name, value);
+            // $09 + 1 is $0A, not BCD $10
+            let result = value.wrapping_add_signed(delta);
+            assert_eq!(decimal[slot], result, "{} ${:02X}", name, value);
+            assert_eq!(decimal[3] & (N | Z), (result & N) | if result == 0 { Z } else { 0 }, "{} ${:02X}", name, value);
+        }
+    }
+}
+
+#[test]
+fn cpx_and_cpy_match_binary_compare_for_every_operand() {
+    let programs = [("CPX", Assembler::new().cpx_imm(0).assemble()), ("CPY", Assembler::new().cpy_imm(0).assemble())];
+    for (name, program) in programs {
+        let load = |cpu:&mut Nmos6502, reg| {
+            cpu.set_x(reg);
+            cpu.set_y(reg);
+        };
+        sweep_immediate(&program, STATUS_CLEAR | D, load, |cpu, reg, operand| {
+            assert_eq!(cpu.get_status() & (N | Z | C), binary_compare_flags(reg, operand), "{} ${:02X} #${:02X}", name, reg, operand);
+        });
+    }
+}
+
+#[test]
+fn the_2a03_adds_and_subtracts_in_binary_with_decimal_set() {
+    let load = |cpu:&mut Nmos6502, a| {
+        cpu.variant = Variant::Nes2A03;
+        cpu.set_a(a);
+    };
+    // carry set: ADC adds one more, SBC borrows nothing
+    sweep_immediate(&Assembler::new().adc_imm(0).assemble(), STATUS_CLEAR | D | C, load, |cpu, a, operand| {
+        let sum = a as u16 + operand as u16 + 1;
+        assert_eq!((cpu.get_a(), cpu.get_status() & C != 0), (sum as u8, sum > 0xFF), "ADC A=${:02X} #${:02X}", a, operand);
+    });
+    sweep_immediate(&Assembler::new().sbc_imm(0).assemble(), STATUS_CLEAR | D | C, load, |cpu, a, operand| {
+        assert_eq!((cpu.get_a(), cpu.get_status() & C != 0), (a.wrapping_sub(operand), a >= operand), "SBC A=${:02X} #${:02X}", a, operand);
+        assert_ne!(cpu.get_status() & D, 0);
+    });
+}
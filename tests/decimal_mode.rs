@@ -0,0 +1,108 @@
+// Bruce Clark's decimal mode test ("Decimal Mode", 6502.org tutorials,
+// appendix B) with its prediction routines ported to Rust. Like the original
+// it runs ADC and SBC for every N1, N2 and carry in and checks A, N, V, Z and
+// C, here against the NMOS 6502 predictions:
+// - A and C follow the NMOS nibble adjust, so invalid BCD digits are defined.
+// - N and V come from the high nibble sum before the final decimal adjust,
+//   taken as a signed value (ADC only).
+// - Z is the binary result's, for ADC too.
+// - SBC's N, V, Z and C are all the binary subtraction's.
+mod common;
+
+use common::*;
+use nmos6502::asm::Assembler;
+use nmos6502::nmos6502::Nmos6502;
+
+// A and the N, V, Z, C bits the NMOS part leaves.
+type Prediction = (u8, u8);
+
+fn flag(set:bool, bit:u8) -> u8 {
+    if set { bit } else { 0 }
+}
+
+// Clark's sequences 1 and 2, plus the binary Z.
+fn predict_adc(n1:u8, n2:u8, carry:bool) -> Prediction {
+    let mut al = (n1 & 0x0F) as i16 + (n2 & 0x0F) as i16 + carry as i16;
+    if al >= 0x0A {
+        al = ((al + 0x06) & 0x0F) + 0x10;
+    }
+    // sequence 2: signed, for N and V
+    let signed = (n1 & 0xF0) as i8 as i16 + (n2 & 0xF0) as i8 as i16 + al;
+    // sequence 1: unsigned, for A and C
+    let mut a = (n1 & 0xF0) as i16 + (n2 & 0xF0) as i16 + al;
+    if a >= 0xA0 {
+        a += 0x60;
+    }
+    let binary = n1.wrapping_add(n2).wrapping_add(carry as u8);
+    let flags = flag(signed & 0x80 != 0, N) | flag(!(-128..=127).contains(&signed), V) | flag(binary == 0, Z) | flag(a >= 0x100, C);
+    (a as u8, flags)
+}
+
+// Clark's sequence 3 for A; the flags are the binary subtraction's.
+fn predict_sbc(n1:u8, n2:u8, carry:bool) -> Prediction {
+    let mut al = (n1 & 0x0F) as i16 - (n2 & 0x0F) as i16 + carry as i16 - 1;
+    if al < 0 {
+        al = ((al - 0x06) & 0x0F) - 0x10;
+    }
+    let mut a = (n1 & 0xF0) as i16 - (n2 & 0xF0) as i16 + al;
+    if a < 0 {
+        a -= 0x60;
+    }
+    let difference = n1 as i16 - n2 as i16 + carry as i16 - 1;
+    let binary = difference as u8;
+    let signed = n1 as i8 as i16 - n2 as i8 as i16 + carry as i16 - 1;
+    let flags = flag(binary & 0x80 != 0, N) | flag(!(-128..=127).contains(&signed), V) | flag(binary == 0, Z) | flag(difference >= 0, C);
+    (a as u8, flags)
+}
+
+// Clark's main loop: `program` is one immediate-mode instruction.
+fn check_all(name:&str, program:Assembler, predict:fn(u8, u8, bool) -> Prediction) {
+    let program = program.assemble();
+    for carry in [false, true] {
+        sweep_immediate(&program, STATUS_CLEAR | D | flag(carry, C), Nmos6502::set_a, |cpu, n1, n2| {
+            let actual = (cpu.get_a(), cpu.get_status() & (N | V | Z | C));
+            assert_eq!(actual, predict(n1, n2, carry), "{} N1=${:02X} N2=${:02X} C={}", name, n1, n2, carry);
+        });
+    }
+}
+
+#[test]
+fn adc_matches_the_nmos_predictions() {
+    check_all("ADC", Assembler::new().adc_imm(0), predict_adc);
+}
+
+#[test]
+fn sbc_matches_the_nmos_predictions() {
+    check_all("SBC", Assembler::new().sbc_imm(0), predict_sbc);
+}
+
+// Spot checks on the predictions themselves, from the tutorial's examples.
+#[test]
+fn predictions_match_the_tutorial() {
+    // 99 + 01 = 00 carry, with Z clear since the binary sum is $9A
+    assert_eq!(predict_adc(0x99, 0x01, false), (0x00, N | C));
+    // 79 + 00 + 1 = 80, and V set as in binary $79 + $01
+    assert_eq!(predict_adc(0x79, 0x00, true), (0x80, N | V));
+    // 00 - 01 = 99 with a borrow
+    assert_eq!(predict_sbc(0x00, 0x01, true), (0x99, N));
+    // 10 - 01 = 09
+    assert_eq!(predict_sbc(0x10, 0x01, true), (0x09, C));
+}
+
+// For valid BCD digits the predictions are plain decimal arithmetic.
+#[test]
+fn predictions_are_decimal_for_valid_bcd() {
+    let bcd = |n:i16| (((n / 10) << 4) | (n % 10)) as u8;
+    for x in 0..100 {
+        for y in 0..100 {
+            for carry in [false, true] {
+                let sum = x + y + carry as i16;
+                let (a, flags) = predict_adc(bcd(x), bcd(y), carry);
+                assert_eq!((a, flags & C != 0), (bcd(sum % 100), sum > 99), "{} + {} + {}", x, y, carry);
+                let difference = x - y - !carry as i16;
+                let (a, flags) = predict_sbc(bcd(x), bcd(y), carry);
+                assert_eq!((a, flags & C != 0), (bcd(difference.rem_euclid(100)), difference >= 0), "{} - {} - {}", x, y, !carry);
+            }
+        }
+    }
+}
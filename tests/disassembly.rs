@@ -0,0 +1,19 @@
+// The monitor's view of the instruction at PC.
+mod common;
+
+use common::*;
+use nmos6502::asm::Assembler;
+
+#[test]
+fn target_description_resolves_an_indexed_load() {
+    let program = Assembler::new().lda_abs_x(0x1234).lda_imm(0x42).assemble();
+    let (mut cpu, mut bus) = setup(&program, STATUS_CLEAR);
+    cpu.set_x(2);
+    bus[0x1236] = 0x42;
+    assert_eq!(cpu.current_target_description(&bus), "LDA $1234,X  ; -> $1236 = $42");
+    // only peeks
+    assert_eq!((cpu.get_pc(), cpu.get_a(), cpu.total_cycles()), (ORIGIN, 0, 0));
+
+    step(&mut cpu, &mut bus);
+    assert_eq!(cpu.current_target_description(&bus), "LDA #$42");
+}
@@ -0,0 +1,47 @@
+// Klaus Dormann's 6502 functional test. The ROM isn't shipped with the crate:
+// build or download 6502_functional_test.bin (the 64K image from the suite's
+// bin_files) into tests/roms/, or point KLAUS_FUNCTIONAL_TEST at it, then run
+// `cargo test --test functional -- --ignored`.
+use std::path::PathBuf;
+
+use nmos6502::flat_bus::FlatBus;
+use nmos6502::nmos6502::{Nmos6502, RunOutcome};
+
+// Where the stock image starts and where it traps once every test has passed.
+const START: u16 = 0x0400;
+const SUCCESS: u16 = 0x3469;
+
+// The full run takes about 96 million cycles.
+const MAX_CYCLES: u64 = 200_000_000;
+const CHUNK: u64 = 100_000;
+
+fn rom_path() -> PathBuf {
+    match std::env::var_os("KLAUS_FUNCTIONAL_TEST") {
+        Some(path) => PathBuf::from(path),
+        None => PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/roms/6502_functional_test.bin"),
+    }
+}
+
+#[test]
+#[ignore = "needs 6502_functional_test.bin, see the top of tests/functional.rs"]
+fn klaus_functional_test() {
+    let path = rom_path();
+    let rom = std::fs::read(&path).unwrap_or_else(|err| panic!("no functional test ROM at {}: {}", path.display(), err));
+    assert_eq!(rom.len(), 0x10000, "expected a 64K image");
+
+    let mut bus = FlatBus::new();
+    bus.load(0, &rom);
+    let mut cpu = Nmos6502::new();
+    cpu.reset_to(START);
+
+    let mut elapsed:u64 = 0;
+    while !cpu.is_at_trap(&bus) {
+        assert!(elapsed < MAX_CYCLES, "no trap after {} cycles, PC ${:04X}", elapsed, cpu.get_pc());
+        let before = cpu.total_cycles();
+        // a trap spins on its own address, so checking between chunks is enough
+        let outcome = cpu.run_until(&mut bus, CHUNK, |_| false);
+        assert_ne!(outcome, RunOutcome::Halted, "halted at ${:04X}", cpu.get_pc());
+        elapsed += cpu.total_cycles() - before;
+    }
+    assert_eq!(cpu.get_pc(), SUCCESS, "trapped at ${:04X}", cpu.get_pc());
+}
@@ -0,0 +1,172 @@
+// Interrupt entry and return.
+mod common;
+
+use common::*;
+use nmos6502::asm::Assembler;
+use nmos6502::flat_bus::FlatBus;
+use nmos6502::nmos6502::{InterruptKind, Nmos6502};
+use nmos6502::processor_status::ProcessorStatus;
+
+// BRK shares the IRQ vector.
+const IRQ_HANDLER: u16 = 0x8000;
+const NMI_HANDLER: u16 = 0x9000;
+
+#[test]
+fn rti_restores_every_flag_from_the_stack() {
+    let (mut cpu, mut bus) = setup(&Assembler::new().rti().assemble(), STATUS_CLEAR);
+    for stacked in 0..=0xFFu8 {
+        // frame at $01FD-$01FF: status, then return address $1234
+        bus.load(0x01FD, &[stacked, 0x34, 0x12]);
+        cpu.set_pc(ORIGIN);
+        cpu.set_stack_pointer(0xFC);
+        cpu.set_status(!stacked);
+        step(&mut cpu, &mut bus);
+        // bits 4 and 5 aren't stored, so they always read set
+        assert_eq!(cpu.get_status(), stacked | 0x30, "stacked ${:02X}", stacked);
+        assert_eq!(cpu.get_pc(), 0x1234);
+        assert_eq!(cpu.get_stack_pointer(), 0xFF);
+    }
+}
+
+#[test]
+fn rti_after_irq_restores_the_interrupted_flags() {
+    let (mut cpu, mut bus) = setup(&Assembler::new().nop().nop().assemble(), STATUS_CLEAR | C | V);
+    bus.load(0xFFFE, &IRQ_HANDLER.to_le_bytes());
+    // the handler changes every flag before returning
+    bus.load(IRQ_HANDLER, &Assembler::new().sed().clc().lda_imm(0x80).rti().assemble());
+
+    cpu.irq = true;
+    step(&mut cpu, &mut bus);
+    cpu.irq = false;
+    assert_eq!(cpu.get_pc(), IRQ_HANDLER);
+    assert_ne!(cpu.get_status() & I, 0);
+
+    run(&mut cpu, &mut bus, 4);
+    assert_eq!(cpu.get_pc(), ORIGIN);
+    assert_eq!(cpu.get_status(), STATUS_CLEAR | C | V);
+}
+
+// A BRK at ORIGIN with an NMI scheduled `in_cycles` into its 7-cycle sequence.
+fn brk_with_nmi_in(in_cycles:u64) -> (Nmos6502, FlatBus) {
+    let (mut cpu, mut bus) = setup(&Assembler::new().brk().nop().assemble(), STATUS_CLEAR);
+    bus.load(0xFFFA, &NMI_HANDLER.to_le_bytes());
+    bus.load(0xFFFE, &IRQ_HANDLER.to_le_bytes());
+    cpu.schedule_nmi(in_cycles);
+    step(&mut cpu, &mut bus);
+    (cpu, bus)
+}
+
+#[test]
+fn nmi_in_the_fourth_cycle_of_brk_hijacks_it() {
+    let (mut cpu, bus) = brk_with_nmi_in(3);
+    assert_eq!(cpu.get_pc(), NMI_HANDLER);
+    assert_eq!(cpu.take_interrupt_ack(), Some(InterruptKind::Nmi));
+    // still BRK's frame: B set, and the NMI is consumed
+    assert_eq!(bus[0x01FD] & 0x10, 0x10);
+    assert!(!cpu.nmi_pending());
+}
+
+#[test]
+fn nmi_in_the_fifth_cycle_of_brk_waits_for_it_to_finish() {
+    let (mut cpu, mut bus) = brk_with_nmi_in(4);
+    assert_eq!(cpu.get_pc(), IRQ_HANDLER);
+    assert_eq!(cpu.take_interrupt_ack(), Some(InterruptKind::Brk));
+    // then taken at the next boundary
+    step(&mut cpu, &mut bus);
+    assert_eq!(cpu.get_pc(), NMI_HANDLER);
+    assert_eq!(cpu.take_interrupt_ack(), Some(InterruptKind::Nmi));
+}
+
+#[test]
+fn brk_stacks_the_address_past_its_signature_byte() {
+    let (mut cpu, mut bus) = setup(&[0x00, 0xEA], STATUS_CLEAR);
+    bus.load(0xFFFE, &IRQ_HANDLER.to_le_bytes());
+    step(&mut cpu, &mut bus);
+    assert_eq!(cpu.get_pc(), IRQ_HANDLER);
+    // return address $0402 above the status byte at $01FD
+    assert_eq!((bus[0x01FE], bus[0x01FF]), (0x02, 0x04));
+    assert_eq!(cpu.get_stack_pointer(), 0xFC);
+}
+
+// The status byte each kind of interrupt stacks, taken from $01FD.
+fn stacked_status(kind:InterruptKind) -> u8 {
+    let (mut cpu, mut bus) = setup(&Assembler::new().brk().nop().assemble(), STATUS_CLEAR | C | N);
+    match kind {
+        InterruptKind::Brk => (),
+        InterruptKind::Irq => cpu.irq = true,
+        InterruptKind::Nmi => cpu.nmi = true,
+    }
+    step(&mut cpu, &mut bus);
+    assert_eq!(cpu.take_interrupt_ack(), Some(kind));
+    bus[0x01FD]
+}
+
+#[test]
+fn only_brk_stacks_the_b_flag() {
+    assert_eq!(stacked_status(InterruptKind::Brk), C | N | 0x30);
+    assert_eq!(stacked_status(InterruptKind::Irq), C | N | 0x20);
+    assert_eq!(stacked_status(InterruptKind::Nmi), C | N | 0x20);
+
+    assert!(ProcessorStatus::from(stacked_status(InterruptKind::Brk)).break_bit());
+    assert!(!ProcessorStatus::from(stacked_status(InterruptKind::Irq)).break_bit());
+    assert!(!ProcessorStatus::from(stacked_status(InterruptKind::Nmi)).break_bit());
+}
+
+// A CPU at ORIGIN running `program` with an IRQ held and its handler at IRQ_HANDLER.
+fn with_irq_held(program:Assembler, status:u8) -> (Nmos6502, FlatBus) {
+    let (mut cpu, mut bus) = setup(&program.assemble(), status);
+    bus.load(0xFFFE, &IRQ_HANDLER.to_le_bytes());
+    cpu.irq = true;
+    (cpu, bus)
+}
+
+#[test]
+fn irq_waits_one_instruction_after_cli() {
+    let (mut cpu, mut bus) = with_irq_held(Assembler::new().cli().inx().inx(), STATUS_CLEAR | I);
+    step(&mut cpu, &mut bus);
+    assert_eq!(cpu.get_status() & I, 0);
+    // I was still set when CLI polled, so the next instruction runs
+    step(&mut cpu, &mut bus);
+    assert_eq!((cpu.get_pc(), cpu.get_x()), (ORIGIN + 2, 1));
+    step(&mut cpu, &mut bus);
+    assert_eq!((cpu.get_pc(), cpu.get_x()), (IRQ_HANDLER, 1));
+    assert_eq!(cpu.take_interrupt_ack(), Some(InterruptKind::Irq));
+}
+
+#[test]
+fn irq_waits_one_instruction_after_plp_clears_i() {
+    let (mut cpu, mut bus) = with_irq_held(Assembler::new().plp().inx().inx(), STATUS_CLEAR | I);
+    // a stacked status with I clear
+    bus[0x01FF] = STATUS_CLEAR;
+    cpu.set_stack_pointer(0xFE);
+    step(&mut cpu, &mut bus);
+    assert_eq!(cpu.get_status() & I, 0);
+    step(&mut cpu, &mut bus);
+    assert_eq!((cpu.get_pc(), cpu.get_x()), (ORIGIN + 2, 1));
+    step(&mut cpu, &mut bus);
+    assert_eq!((cpu.get_pc(), cpu.get_x()), (IRQ_HANDLER, 1));
+}
+
+// How many instructions run after a BNE with `offset` before an IRQ raised
+// during it is taken. Every place the branch can land holds INXs.
+fn instructions_after_branch(status:u8, offset:i8) -> u8 {
+    let (mut cpu, mut bus) = with_irq_held(Assembler::new().bne(offset), status);
+    cpu.irq = false;
+    let inxs = Assembler::new().inx().inx().inx().assemble();
+    bus.load(ORIGIN + 2, &inxs);
+    bus.load((ORIGIN + 2).wrapping_add_signed(offset as i16), &inxs);
+    step(&mut cpu, &mut bus);
+    cpu.irq = true;
+    while cpu.get_pc() != IRQ_HANDLER {
+        step(&mut cpu, &mut bus);
+    }
+    cpu.get_x()
+}
+
+#[test]
+fn taken_branch_without_page_cross_delays_an_irq() {
+    assert_eq!(instructions_after_branch(STATUS_CLEAR, 0x10), 1);
+    // not taken, and taken across a page, poll as usual
+    assert_eq!(instructions_after_branch(STATUS_CLEAR | Z, 0x10), 0);
+    assert_eq!(instructions_after_branch(STATUS_CLEAR, -0x10), 0);
+}
@@ -0,0 +1,56 @@
+// The 6510's port registers at $0000/$0001 stand in for the bus on every read,
+// instruction fetches included.
+mod common;
+
+use common::*;
+use nmos6502::variant::Variant;
+
+#[test]
+fn opcode_fetch_at_zero_reads_the_direction_register() {
+    let (mut cpu, mut bus) = setup(&[], STATUS_CLEAR);
+    cpu.variant = Variant::Mos6510;
+    bus[0x0000] = 0x00; // BRK on the bus
+    cpu.port_ddr = 0xEA; // NOP
+    cpu.set_pc(0x0000);
+    assert_eq!(step(&mut cpu, &mut bus), 2);
+    assert_eq!(cpu.get_pc(), 0x0001);
+}
+
+#[test]
+fn operand_fetch_reads_the_port() {
+    let (mut cpu, mut bus) = setup(&[], STATUS_CLEAR);
+    cpu.variant = Variant::Mos6510;
+    // LDA #$0000 straddling the top of memory: the operand comes from $0000.
+    bus[0xFFFF] = 0xA9;
+    bus[0x0000] = 0x55;
+    cpu.port_ddr = 0x2F;
+    cpu.set_pc(0xFFFF);
+    step(&mut cpu, &mut bus);
+    assert_eq!(cpu.get_a(), 0x2F);
+    assert_eq!(cpu.get_pc(), 0x0001);
+
+    // LDA $nnnn from $0000: opcode from the direction register, the low
+    // operand byte from the data port's pin levels, $12 with inputs high.
+    cpu.port_ddr = 0xAD;
+    cpu.port_data = 0x00;
+    bus[0x0001] = 0x34;
+    bus[0x0002] = 0x20;
+    bus[0x2012] = 0x77;
+    bus[0x2034] = 0x66;
+    cpu.set_pc(0x0000);
+    step(&mut cpu, &mut bus);
+    assert_eq!(cpu.io_port(), 0x12);
+    assert_eq!(cpu.get_a(), 0x77);
+    assert_eq!(cpu.get_pc(), 0x0003);
+}
+
+#[test]
+fn other_variants_fetch_zero_page_from_the_bus() {
+    let (mut cpu, mut bus) = setup(&[], STATUS_CLEAR);
+    bus[0x0000] = 0xA9;
+    bus[0x0001] = 0x55;
+    cpu.port_ddr = 0xEA;
+    cpu.set_pc(0x0000);
+    step(&mut cpu, &mut bus);
+    assert_eq!(cpu.get_a(), 0x55);
+}
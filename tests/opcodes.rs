@@ -0,0 +1,76 @@
+// Opcode enumeration and decoding.
+mod common;
+
+use nmos6502::flat_bus::FlatBus;
+use nmos6502::nmos6502::{HaltReason, Nmos6502};
+use nmos6502::opcodes::{AddressingMode, Opcode};
+use nmos6502::variant::Variant;
+
+const VARIANTS: [Variant; 4] = [Variant::Nmos6502, Variant::Cmos65C02, Variant::Nes2A03, Variant::Mos6510];
+
+#[test]
+fn all_covers_every_decodable_byte_of_every_variant() {
+    let all:Vec<Opcode> = Opcode::all().collect();
+    assert!(!all.contains(&Opcode::UNREC));
+    for (i, op) in all.iter().enumerate() {
+        assert!(!all[..i].contains(op), "{:?} listed twice", op);
+    }
+    for variant in VARIANTS {
+        let decodable:Vec<Opcode> = (0..=0xFFu8).map(|byte| Opcode::decode(byte, variant)).filter(|op| *op != Opcode::UNREC).collect();
+        for op in &decodable {
+            assert!(all.contains(op), "{:?} {:?} missing", variant, op);
+        }
+        assert_eq!(all.iter().filter(|op| op.exists_on(variant)).count(), decodable.len(), "{:?}", variant);
+    }
+}
+
+#[test]
+fn all_includes_the_65c02_additions() {
+    for op in [Opcode::RMB0, Opcode::BBS7, Opcode::TSBz, Opcode::TRBabs, Opcode::LDAindZ, Opcode::JMPiX] {
+        assert!(Opcode::all().any(|listed| listed == op), "{:?}", op);
+        assert!(op.exists_on(Variant::Cmos65C02) && !op.exists_on(Variant::Nmos6502));
+    }
+}
+
+#[test]
+fn encode_stays_nmos_only() {
+    assert_eq!(Opcode::encode("lda", AddressingMode::Immediate), Some(Opcode::LDAimm));
+    assert_eq!(Opcode::encode("LDA", AddressingMode::ZeroPageIndirect), None);
+    assert_eq!(Opcode::encode("TSB", AddressingMode::ZeroPage), None);
+}
+
+#[test]
+fn from_u8_is_the_nmos_decode() {
+    for byte in 0..=0xFFu8 {
+        assert_eq!(Opcode::from(byte), Opcode::decode(byte, Variant::Nmos6502), "${:02X}", byte);
+    }
+    // as before the 65C02 opcodes existed
+    assert_eq!(Opcode::from(0xFFu8), Opcode::UNREC);
+    assert_eq!(Opcode::from(0x02u8), Opcode::UNREC);
+    assert_eq!(Opcode::from(0x07u8), Opcode::UNREC);
+    assert_eq!(Opcode::from(0x04u8), Opcode::NOPz0);
+    assert_eq!(Opcode::from(0xA9u8), Opcode::LDAimm);
+}
+
+#[test]
+fn as_byte_round_trips_through_decode() {
+    for op in Opcode::all() {
+        let variant = if op.exists_on(Variant::Nmos6502) { Variant::Nmos6502 } else { Variant::Cmos65C02 };
+        assert_eq!(Opcode::decode(op.as_byte(), variant), op, "{:?}", op);
+    }
+    assert_eq!(Opcode::BBS7.as_byte(), 0xFF);
+    assert_eq!(Opcode::TSBz.as_byte(), 0x04);
+}
+
+#[test]
+fn every_opcode_has_a_handler() {
+    for variant in VARIANTS {
+        for op in Opcode::all().filter(|op| op.exists_on(variant)) {
+            let mut cpu = Nmos6502::new();
+            cpu.variant = variant;
+            let mut bus = FlatBus::new();
+            cpu.execute_raw(&mut bus, op.as_byte(), 0, 0);
+            assert!(!matches!(cpu.halt_reason(), Some(HaltReason::UnknownOpcode(..))), "{:?} on {:?}", op, variant);
+        }
+    }
+}
@@ -0,0 +1,35 @@
+// Per addressing mode cycle totals while profiling.
+mod common;
+
+use common::*;
+use nmos6502::asm::Assembler;
+use nmos6502::opcodes::AddressingMode;
+
+#[test]
+fn addressing_mode_buckets_sum_to_total_cycles() {
+    let program = Assembler::new()
+        .lda_imm(0x10)
+        .sta_zp(0x20)
+        .ldx_imm(0xF0)
+        .lda_abs_x(0x12F8)
+        .sta_ind_y(0x20)
+        .asl_a()
+        .inc_abs(0x0300)
+        .bne(-5)
+        .jmp_ind(0x0310)
+        .assemble();
+    let (mut cpu, mut bus) = setup(&program, STATUS_CLEAR);
+    bus.load(0x0310, &ORIGIN.to_le_bytes());
+    cpu.profiling = true;
+    run(&mut cpu, &mut bus, 1000);
+
+    let buckets = cpu.cycles_by_addressing_mode();
+    assert_eq!(buckets.iter().sum::<u64>(), cpu.total_cycles());
+    for mode in [AddressingMode::Immediate, AddressingMode::ZeroPage, AddressingMode::AbsoluteX,
+                 AddressingMode::IndirectY, AddressingMode::Accumulator, AddressingMode::Absolute,
+                 AddressingMode::Relative, AddressingMode::Indirect] {
+        assert_ne!(buckets[mode as usize], 0, "{:?}", mode);
+    }
+    assert_eq!(buckets[AddressingMode::ZeroPageIndirect as usize], 0);
+}
+
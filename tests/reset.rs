@@ -0,0 +1,68 @@
+// Reset runs the interrupt sequence with the writes suppressed.
+mod common;
+
+use common::*;
+use nmos6502::asm::Assembler;
+use nmos6502::nmos6502::HaltReason;
+use nmos6502::variant::Variant;
+
+#[test]
+fn each_reset_lowers_sp_and_sets_i() {
+    let (mut cpu, mut bus) = setup(&[], STATUS_CLEAR);
+    bus.load(0xFFFC, &[0x00, 0xC0]);
+    cpu.reset(&mut bus);
+    assert_eq!((cpu.get_pc(), cpu.get_stack_pointer()), (0xC000, 0xFC));
+    assert_ne!(cpu.get_status() & I, 0);
+    cpu.reset(&mut bus);
+    assert_eq!(cpu.get_stack_pointer(), 0xF9);
+    // nothing is pushed
+    assert!((0x0100..=0x01FF).all(|addr| bus[addr] == 0));
+}
+
+#[test]
+fn reset_to_skips_the_vector() {
+    let (mut cpu, _) = setup(&[], STATUS_CLEAR);
+    cpu.reset_to(0x1234);
+    assert_eq!((cpu.get_pc(), cpu.get_stack_pointer()), (0x1234, 0xFC));
+    assert_ne!(cpu.get_status() & I, 0);
+}
+
+#[test]
+fn reset_makes_the_6510_port_all_inputs() {
+    let (mut cpu, mut bus) = setup(&[], STATUS_CLEAR);
+    bus.load(0xFFFC, &[0x00, 0xC0]);
+    cpu.variant = Variant::Mos6510;
+    cpu.port_ddr = 0x2F;
+    cpu.reset(&mut bus);
+    assert_eq!(cpu.port_ddr, 0);
+
+    cpu.port_ddr = 0x2F;
+    cpu.reset_to(0x1234);
+    assert_eq!(cpu.port_ddr, 0);
+}
+
+#[test]
+fn power_on_gives_the_cold_start_state() {
+    let (mut cpu, mut bus) = setup(&[], STATUS_CLEAR | C | N);
+    bus.load(0xFFFC, &[0x00, 0xC0]);
+    cpu.set_stack_pointer(0x12);
+    cpu.power_on(&mut bus);
+    assert_eq!((cpu.get_pc(), cpu.get_stack_pointer(), cpu.get_status()), (0xC000, 0xFD, STATUS_CLEAR | I));
+}
+
+#[test]
+fn a_jammed_cpu_stays_put_until_reset() {
+    let (mut cpu, mut bus) = setup(&Assembler::new().inx().assemble(), STATUS_CLEAR);
+    bus.load(0xFFFC, &[0x00, 0x04]);
+    cpu.jam();
+    assert_eq!(cpu.halt_reason(), Some(HaltReason::Jam));
+    for _ in 0..3 {
+        cpu.tick(&mut bus);
+    }
+    assert_eq!((cpu.get_pc(), cpu.get_x(), cpu.total_cycles()), (ORIGIN, 0, 0));
+
+    cpu.reset(&mut bus);
+    assert_eq!(cpu.halt_reason(), None);
+    step(&mut cpu, &mut bus);
+    assert_eq!((cpu.get_pc(), cpu.get_x()), (ORIGIN + 1, 1));
+}
@@ -0,0 +1,98 @@
+// ASL, LSR, ROL and ROR on the accumulator and in memory.
+mod common;
+
+use common::*;
+use nmos6502::asm::Assembler;
+
+#[derive(Copy,Clone,Debug)]
+enum Shift {
+    Asl,
+    Lsr,
+    Rol,
+    Ror,
+}
+
+// Result and carry out of shifting `value` with carry in `carry`.
+fn reference(shift:Shift, value:u8, carry:bool) -> (u8, bool) {
+    match shift {
+        Shift::Asl => (value << 1, value & 0x80 != 0),
+        Shift::Lsr => (value >> 1, value & 0x01 != 0),
+        Shift::Rol => ((value << 1) | carry as u8, value & 0x80 != 0),
+        Shift::Ror => ((value >> 1) | ((carry as u8) << 7), value & 0x01 != 0),
+    }
+}
+
+// Result and N, Z, C after running `program` on `value`, held in A and at $10.
+fn shifted(program:&[u8], value:u8, carry:bool, in_memory:bool) -> (u8, u8) {
+    let (mut cpu, mut bus) = setup(program, STATUS_CLEAR | if carry { C } else { 0 });
+    cpu.set_a(value);
+    bus[0x0010] = value;
+    step(&mut cpu, &mut bus);
+    let result = if in_memory { bus[0x0010] } else { cpu.get_a() };
+    (result, cpu.get_status() & (N | Z | C))
+}
+
+fn programs() -> [(Shift, &'static str, Vec<u8>, bool); 8] {
+    [
+        (Shift::Asl, "ASL A", Assembler::new().asl_a().assemble(), false),
+        (Shift::Asl, "ASL $10", Assembler::new().asl_zp(0x10).assemble(), true),
+        (Shift::Lsr, "LSR A", Assembler::new().lsr_a().assemble(), false),
+        (Shift::Lsr, "LSR $10", Assembler::new().lsr_zp(0x10).assemble(), true),
+        (Shift::Rol, "ROL A", Assembler::new().rol_a().assemble(), false),
+        (Shift::Rol, "ROL $10", Assembler::new().rol_zp(0x10).assemble(), true),
+        (Shift::Ror, "ROR A", Assembler::new().ror_a().assemble(), false),
+        (Shift::Ror, "ROR $10", Assembler::new().ror_zp(0x10).assemble(), true),
+    ]
+}
+
+#[test]
+fn boundary_values() {
+    // (value, carry in) -> (result, flags) for ASL, LSR, ROL, ROR
+    let cases = [
+        (0x00, false, [(0x00, Z), (0x00, Z), (0x00, Z), (0x00, Z)]),
+        (0x00, true, [(0x00, Z), (0x00, Z), (0x01, 0), (0x80, N)]),
+        (0x01, false, [(0x02, 0), (0x00, Z | C), (0x02, 0), (0x00, Z | C)]),
+        (0x01, true, [(0x02, 0), (0x00, Z | C), (0x03, 0), (0x80, N | C)]),
+        (0x80, false, [(0x00, Z | C), (0x40, 0), (0x00, Z | C), (0x40, 0)]),
+        (0x80, true, [(0x00, Z | C), (0x40, 0), (0x01, C), (0xC0, N)]),
+        (0xFF, false, [(0xFE, N | C), (0x7F, C), (0xFE, N | C), (0x7F, C)]),
+        (0xFF, true, [(0xFE, N | C), (0x7F, C), (0xFF, N | C), (0xFF, N | C)]),
+    ];
+    for (shift, name, program, in_memory) in programs() {
+        for (value, carry, expected) in cases {
+            assert_eq!(shifted(&program, value, carry, in_memory), expected[shift as usize], "{} ${:02X} C={}", name, value, carry);
+        }
+    }
+}
+
+#[test]
+fn every_value_matches_the_reference() {
+    for (shift, name, program, in_memory) in programs() {
+        for value in 0..=0xFFu8 {
+            for carry in [false, true] {
+                let (result, carry_out) = reference(shift, value, carry);
+                let flags = (result & N) | if result == 0 { Z } else { 0 } | if carry_out { C } else { 0 };
+                assert_eq!(shifted(&program, value, carry, in_memory), (result, flags), "{} ${:02X} C={}", name, value, carry);
+            }
+        }
+    }
+}
+
+#[test]
+fn lsr_cycles_by_addressing_mode() {
+    let programs = [
+        ("LSR A", Assembler::new().lsr_a(), 2),
+        ("LSR zp", Assembler::new().lsr_zp(0x10), 5),
+        ("LSR zp,X", Assembler::new().lsr_zp_x(0x10), 6),
+        ("LSR abs", Assembler::new().lsr_abs(0x1234), 6),
+        ("LSR abs,X", Assembler::new().lsr_abs_x(0x1234), 7),
+    ];
+    for (name, program, expected) in programs {
+        // X crosses a page for abs,X, which still takes 7
+        for x in [0x00, 0xFF] {
+            let (mut cpu, mut bus) = setup(&program.clone().assemble(), STATUS_CLEAR);
+            cpu.set_x(x);
+            assert_eq!(step(&mut cpu, &mut bus), expected, "{} X=${:02X}", name, x);
+        }
+    }
+}
@@ -0,0 +1,78 @@
+// The stack lives in page 1 and wraps within it, unless extended_stack is set.
+mod common;
+
+use common::*;
+use nmos6502::asm::Assembler;
+use nmos6502::flat_bus::FlatBus;
+use nmos6502::nmos6502::Nmos6502;
+
+#[test]
+fn jsr_at_sp_zero_wraps_within_page_one() {
+    let (mut cpu, mut bus) = setup(&Assembler::new().jsr_abs(0x0500).assemble(), STATUS_CLEAR);
+    bus.load(0x0500, &Assembler::new().rts().assemble());
+    cpu.set_stack_pointer(0x00);
+    step(&mut cpu, &mut bus);
+
+    // return address $0402: high byte at SP=$00, low byte after the wrap to $FF
+    assert_eq!(bus[0x0100], 0x04);
+    assert_eq!(bus[0x01FF], 0x02);
+    assert_eq!(cpu.get_stack_pointer(), 0xFE);
+    assert_eq!(bus[0x0000], 0x00);
+    assert_eq!(bus[0x0200], 0x00);
+
+    step(&mut cpu, &mut bus);
+    assert_eq!(cpu.get_pc(), 0x0403);
+    assert_eq!(cpu.get_stack_pointer(), 0x00);
+}
+
+#[test]
+fn pull_at_sp_ff_wraps_to_the_page_bottom() {
+    let (mut cpu, mut bus) = setup(&Assembler::new().pla().assemble(), STATUS_CLEAR);
+    bus[0x0100] = 0x5A;
+    bus[0x0200] = 0xA5;
+    cpu.set_stack_pointer(0xFF);
+    step(&mut cpu, &mut bus);
+    assert_eq!(cpu.get_a(), 0x5A);
+    assert_eq!(cpu.get_stack_pointer(), 0x00);
+}
+
+// JSR $0500 into a routine that recurses X levels deep, leaving the CPU at
+// the innermost RTS: 200 return addresses, 400 bytes of stack.
+fn recurse(extended:bool) -> (Nmos6502, FlatBus) {
+    let (mut cpu, mut bus) = setup(&Assembler::new().jsr_abs(0x0500).assemble(), STATUS_CLEAR);
+    bus.load(0x0500, &Assembler::new().dex().beq(3).jsr_abs(0x0500).rts().assemble());
+    cpu.extended_stack = extended;
+    cpu.set_x(200);
+    cpu.run_until(&mut bus, 100_000, |cpu| cpu.get_pc() == 0x0506);
+    (cpu, bus)
+}
+
+#[test]
+fn extended_stack_runs_past_page_one_without_wrapping() {
+    let (mut cpu, mut bus) = recurse(true);
+    assert_eq!(cpu.get_extended_stack_pointer(), 0x01FF - 400);
+    // the outermost return address, $0402, is still intact
+    assert_eq!((bus[0x01FE], bus[0x01FF]), (0x02, 0x04));
+
+    cpu.run_until(&mut bus, 100_000, |cpu| cpu.get_pc() == 0x0403);
+    assert_eq!(cpu.get_extended_stack_pointer(), 0x01FF);
+}
+
+#[test]
+fn real_stack_wraps_onto_its_own_frames() {
+    let (cpu, bus) = recurse(false);
+    assert_eq!(cpu.get_extended_stack_pointer(), 0x016F);
+    // the 129th return address, $0505, landed on the outermost one
+    assert_eq!((bus[0x01FE], bus[0x01FF]), (0x05, 0x05));
+}
+
+#[test]
+fn reset_puts_an_extended_stack_back_on_page_one() {
+    let (mut cpu, _) = recurse(true);
+    cpu.reset_to(ORIGIN);
+    assert_eq!(cpu.get_extended_stack_pointer() & 0xFF00, 0x0100);
+
+    let (mut cpu, _) = recurse(true);
+    cpu.set_stack_pointer(0xFF);
+    assert_eq!(cpu.get_extended_stack_pointer(), 0x01FF);
+}
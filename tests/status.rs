@@ -0,0 +1,21 @@
+// The B flag and bit 5 as pushed and pulled.
+use nmos6502::processor_status::ProcessorStatus;
+
+#[test]
+fn stack_byte_sets_bit_five_and_b_only_for_brk() {
+    for byte in 0..=0xFFu8 {
+        let status = ProcessorStatus::from(byte);
+        assert_eq!(status.as_stack_byte(true), byte | 0x30, "${:02X}", byte);
+        assert_eq!(status.as_stack_byte(false), (byte & !0x10) | 0x20, "${:02X}", byte);
+    }
+}
+
+#[test]
+fn from_stack_ignores_bits_four_and_five() {
+    for byte in 0..=0xFFu8 {
+        let status = ProcessorStatus::from_stack(byte);
+        assert_eq!(status.as_byte(), byte | 0x30, "${:02X}", byte);
+        // so pulling what was pushed never depends on how it was pushed
+        assert_eq!(ProcessorStatus::from_stack(status.as_stack_byte(false)), status, "${:02X}", byte);
+    }
+}
@@ -0,0 +1,47 @@
+// What step reports, and when it reports nothing.
+mod common;
+
+use common::*;
+use nmos6502::asm::Assembler;
+use nmos6502::nmos6502::BreakpointKind;
+
+#[test]
+fn step_describes_the_instruction_it_ran() {
+    let program = Assembler::new().lda_imm(0x42).sta_abs(0x1234).assemble();
+    let (mut cpu, mut bus) = setup(&program, STATUS_CLEAR);
+
+    let first = cpu.step(&mut bus).unwrap();
+    assert_eq!((first.pc, first.opcode, first.cycles), (ORIGIN, 0xA9, 2));
+    assert_eq!(first.disassembly.to_string(), "LDA #$42");
+
+    let second = cpu.step(&mut bus).unwrap();
+    assert_eq!((second.pc, second.opcode, second.cycles), (ORIGIN + 2, 0x8D, 4));
+    assert_eq!(second.disassembly.to_string(), "STA $1234");
+}
+
+#[test]
+fn step_reports_nothing_for_an_interrupt() {
+    let (mut cpu, mut bus) = setup(&Assembler::new().nop().assemble(), STATUS_CLEAR);
+    cpu.step(&mut bus).unwrap();
+    cpu.irq = true;
+    assert_eq!(cpu.step(&mut bus), None);
+}
+
+#[test]
+fn step_reports_nothing_while_halted() {
+    let (mut cpu, mut bus) = setup(&Assembler::new().nop().assemble(), STATUS_CLEAR);
+    cpu.step(&mut bus).unwrap();
+    cpu.jam();
+    assert_eq!(cpu.step(&mut bus), None);
+}
+
+#[test]
+fn step_reports_nothing_when_stopping_at_a_breakpoint() {
+    let (mut cpu, mut bus) = setup(&Assembler::new().nop().inx().assemble(), STATUS_CLEAR);
+    cpu.add_breakpoint(ORIGIN + 1, BreakpointKind::Exec);
+    cpu.step(&mut bus).unwrap();
+    assert_eq!(cpu.step(&mut bus), None);
+    assert_eq!(cpu.hit_breakpoint, Some(ORIGIN + 1));
+    // resumes past it
+    assert_eq!(cpu.step(&mut bus).unwrap().disassembly.to_string(), "INX");
+}
@@ -0,0 +1,44 @@
+// Comparing instruction traces.
+mod common;
+
+use common::*;
+use nmos6502::asm::Assembler;
+use nmos6502::trace::{diff_traces, InstructionRecord, TraceField};
+
+// The record after each of `count` instructions of `program`.
+fn trace(program:Assembler, count:usize) -> Vec<InstructionRecord> {
+    let (mut cpu, mut bus) = setup(&program.assemble(), STATUS_CLEAR);
+    (0..count).map(|_| {
+        step(&mut cpu, &mut bus);
+        cpu.instruction_record()
+    }).collect()
+}
+
+#[test]
+fn diff_reports_the_first_diverging_instruction_and_field() {
+    let expected = trace(Assembler::new().ldx_imm(1).lda_imm(0x10).inx().tax(), 4);
+    let actual = trace(Assembler::new().ldx_imm(1).lda_imm(0x90).inx().tax(), 4);
+
+    let divergence = diff_traces(&expected, &actual).unwrap();
+    assert_eq!(divergence.index, 1);
+    // A and N both differ; A comes first
+    assert_eq!(divergence.field, TraceField::Accumulator);
+    assert_eq!(divergence.expected, Some(expected[1]));
+    assert_eq!(divergence.actual, Some(actual[1]));
+    assert_eq!(divergence.to_string(), "instruction 1 at $0402: Accumulator expected $10, got $90");
+
+    assert_eq!(expected[1].first_difference(&actual[1]), Some(TraceField::Accumulator));
+    assert_eq!(expected[3].first_difference(&actual[3]), Some(TraceField::Accumulator));
+    assert_eq!(expected[2].first_difference(&actual[2]), Some(TraceField::Accumulator));
+    assert_eq!(expected[0].first_difference(&actual[0]), None);
+}
+
+#[test]
+fn diff_reports_a_trace_that_ends_early() {
+    let expected = trace(Assembler::new().nop().nop().nop(), 3);
+    let divergence = diff_traces(&expected, &expected[..2]).unwrap();
+    assert_eq!((divergence.index, divergence.field), (2, TraceField::Length));
+    assert_eq!(divergence.actual, None);
+    assert_eq!(divergence.to_string(), "instruction 2: actual trace ended early");
+    assert_eq!(diff_traces(&expected, &expected), None);
+}